@@ -1,9 +1,16 @@
 import winston from "winston";
 
+import { SCHEDULE_MODE } from "./configs";
+import { FatalError } from "./errors";
 import { FlowMetricRecorder } from "./flowMetric";
+import { MIN, SEC, timeoutPromise } from "./utils";
 
 import type { Logger } from "winston";
 
+/// number of consecutive restarts after which a crashing flow loop is given up
+const LOOP_RESTART_LIMIT = +(process.env.LOOP_RESTART_LIMIT ?? 10);
+const LOOP_RESTART_MAX_BACKOFF = 5 * MIN;
+
 /**
  * Base class for all flows that provides common logging functionality
  */
@@ -15,4 +22,64 @@ export abstract class BaseFlow {
     this.logger = winston.child({ flowName });
     this.metricRecorder = new FlowMetricRecorder(flowName, this.logger);
   }
+
+  /** The flow loop, expected to run forever. */
+  public abstract run(): Promise<void>;
+
+  /**
+   * Runs the flow loop and restarts it with exponential backoff if it crashes, so that a single flow doesn't
+   * silently stop emitting while the others keep running. Gives up after `LOOP_RESTART_LIMIT` consecutive
+   * restarts, reported in `watchdog_loop_alive`. A `FatalError` is never restarted on, it's rethrown for `main`.
+   */
+  public async runSupervised(): Promise<void> {
+    for (let restarts = 0; ; restarts++) {
+      const start = Date.now();
+      this.metricRecorder.recordLoopAlive(true);
+      try {
+        await this.run();
+        this.logger.error("Flow loop exited unexpectedly");
+        // eslint-disable-next-line @typescript-eslint/no-explicit-any
+      } catch (error: any) {
+        if (error instanceof FatalError) {
+          this.metricRecorder.recordLoopAlive(false);
+          throw error;
+        }
+        this.logger.error("Flow loop crashed: " + error?.message, error?.stack);
+      }
+      this.metricRecorder.recordLoopAlive(false);
+      // a loop that ran for a while before crashing starts over with the shortest backoff
+      if (Date.now() - start > LOOP_RESTART_MAX_BACKOFF) restarts = 0;
+      if (restarts >= LOOP_RESTART_LIMIT) {
+        this.logger.error(`Flow loop crashed ${restarts + 1} times in a row, giving up`);
+        return;
+      }
+      const backoff = Math.min(SEC * 2 ** restarts, LOOP_RESTART_MAX_BACKOFF);
+      this.logger.warn(`Restarting flow loop in ${backoff / SEC} seconds`);
+      await timeoutPromise(backoff);
+      this.metricRecorder.recordLoopRestart();
+    }
+  }
+
+  /**
+   * To be called at the start of each cycle of the flow loop, returns the wait before the next cycle.
+   * In `fixed_rate` mode cycles start every `intervalMs`, ticks overrun by the previous cycle are skipped.
+   * In `fixed_delay` mode the flow waits `intervalMs` after the end of each cycle.
+   */
+  protected startCycle(intervalMs: number): () => Promise<void> {
+    const start = Date.now();
+    return async () => {
+      if (SCHEDULE_MODE === "fixed_delay") {
+        return timeoutPromise(intervalMs);
+      }
+      const elapsed = Date.now() - start;
+      if (elapsed > intervalMs) {
+        const skipped = Math.floor(elapsed / intervalMs);
+        this.metricRecorder.recordTickSkipped(skipped);
+        this.logger.warn(
+          `Cycle took ${elapsed} ms, longer than the ${intervalMs} ms interval. Skipping ${skipped} tick(s)`
+        );
+      }
+      return timeoutPromise(intervalMs - (elapsed % intervalMs));
+    };
+  }
 }
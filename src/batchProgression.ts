@@ -0,0 +1,113 @@
+import "dotenv/config";
+
+import { Gauge } from "prom-client";
+
+import { BaseFlow } from "./baseFlow";
+import { SEC } from "./utils";
+
+import type { Provider } from "zksync-ethers";
+
+const FLOW_NAME = "batch_progression";
+
+/// `zks_getL1BatchDetails` field that is set once the batch reached the stage
+const STAGE_FIELDS = {
+  committed: "committedAt",
+  proven: "provenAt",
+  executed: "executedAt",
+} as const;
+type Stage = keyof typeof STAGE_FIELDS;
+
+/**
+ * Monitors the commit -> prove -> execute pipeline independently of our own transactions by tracking the
+ * latest L1 batch that reached each stage. A growing gap to the latest sealed batch means the proving or
+ * settlement pipeline is stalling.
+ */
+export class BatchProgressionFlow extends BaseFlow {
+  private metricBatchNumber: Gauge;
+  private metricBatchGap: Gauge;
+  /// last batch known to have reached each stage, the stages only ever move forward
+  private lastKnown: Record<Stage, number> = { committed: 0, proven: 0, executed: 0 };
+
+  constructor(
+    private provider: Provider,
+    private intervalMs: number
+  ) {
+    super(FLOW_NAME);
+    this.metricBatchNumber = new Gauge({
+      name: "watchdog_l1_batch_number",
+      help: "Latest L1 batch number per stage (sealed, committed, proven, executed)",
+      labelNames: ["stage"],
+    });
+    this.metricBatchGap = new Gauge({
+      name: "watchdog_l1_batch_gap",
+      help: "Number of batches between the latest sealed batch and the latest batch of the stage",
+      labelNames: ["stage"],
+    });
+  }
+
+  private async hasReachedStage(batch: number, stage: Stage): Promise<boolean> {
+    const details = (await this.provider.send("zks_getL1BatchDetails", [batch])) as Record<string, unknown> | null;
+    return details?.[STAGE_FIELDS[stage]] != null;
+  }
+
+  /**
+   * Finds the latest batch in `[lowerBound, sealed]` that reached `stage`, given that `lowerBound` did.
+   * Gallops forward from the lower bound and then bisects, so the steady state costs only a few requests.
+   */
+  private async findLatestBatch(stage: Stage, lowerBound: number, sealed: number): Promise<number> {
+    let low = lowerBound;
+    let high = sealed + 1; // first batch known not to have reached the stage
+    for (let step = 1; low + step <= sealed; step *= 2) {
+      if (!(await this.hasReachedStage(low + step, stage))) {
+        high = low + step;
+        break;
+      }
+      low += step;
+    }
+    while (high - low > 1) {
+      const mid = Math.floor((low + high) / 2);
+      if (await this.hasReachedStage(mid, stage)) {
+        low = mid;
+      } else {
+        high = mid;
+      }
+    }
+    return low;
+  }
+
+  public async run() {
+    while (true) {
+      const waitNextExecution = this.startCycle(this.intervalMs);
+
+      try {
+        this.metricRecorder.recordFlowStart();
+
+        await this.metricRecorder.stepExecution({
+          stepName: "batch_progression",
+          stepTimeoutMs: 30 * SEC,
+          fn: async () => {
+            const sealed = Number(await this.provider.send("zks_L1BatchNumber", []));
+            this.metricBatchNumber.set({ stage: "sealed" }, sealed);
+            for (const stage of Object.keys(STAGE_FIELDS) as Stage[]) {
+              const latest = await this.findLatestBatch(stage, Math.min(this.lastKnown[stage], sealed), sealed);
+              this.lastKnown[stage] = latest;
+              this.metricBatchNumber.set({ stage }, latest);
+              this.metricBatchGap.set({ stage }, sealed - latest);
+            }
+            this.logger.debug(
+              `Sealed batch ${sealed}, committed ${this.lastKnown.committed}, proven ${this.lastKnown.proven}, executed ${this.lastKnown.executed}`
+            );
+          },
+        });
+
+        this.metricRecorder.recordFlowSuccess();
+        // eslint-disable-next-line @typescript-eslint/no-explicit-any
+      } catch (error: any) {
+        this.logger.error("Batch progression error: " + error?.message, error?.stack);
+        this.metricRecorder.recordFlowFailure();
+      }
+
+      await waitNextExecution();
+    }
+  }
+}
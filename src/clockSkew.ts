@@ -0,0 +1,90 @@
+import "dotenv/config";
+import { Gauge } from "prom-client";
+
+import { BaseFlow } from "./baseFlow";
+import { SEC, unwrap } from "./utils";
+
+import type { Provider as EthersProvider } from "ethers";
+import type { Logger } from "winston";
+
+const FLOW_NAME = "clock_skew";
+/// skew (either way) above which the validity window of native transactions signed locally can't be trusted
+const CLOCK_SKEW_TOLERANCE_SECONDS = +(process.env.FLOW_CLOCK_SKEW_TOLERANCE_SECONDS ?? 30);
+
+/// last skew measured by the flow in seconds, null if the flow isn't enabled or hasn't measured yet
+let lastClockSkew: number | null = null;
+
+const metricClockSkewExceeded = new Gauge({
+  name: "watchdog_clock_skew_exceeded",
+  help: "1 if the last measured clock skew exceeds FLOW_CLOCK_SKEW_TOLERANCE_SECONDS, 0 otherwise",
+});
+
+/**
+ * Warns before signing a native (EIP-712) transaction while the measured clock skew exceeds the tolerance, as the
+ * node may then reject it for its validity window instead of the watchdog silently producing doomed transactions.
+ */
+export function warnIfClockSkewExceeded(logger: Logger) {
+  if (lastClockSkew != null && Math.abs(lastClockSkew) > CLOCK_SKEW_TOLERANCE_SECONDS) {
+    logger.warn(
+      `Signing a native transaction while the clock skew is ${lastClockSkew.toFixed(1)} seconds ` +
+        `(tolerance ${CLOCK_SKEW_TOLERANCE_SECONDS}), the node may reject it`
+    );
+  }
+}
+
+/**
+ * Compares the timestamp of the latest block against the local clock. A large skew explains anomalous
+ * latency numbers and points to either node time issues or drift of the watchdog clock.
+ */
+export class ClockSkewFlow extends BaseFlow {
+  private metricClockSkew: Gauge;
+
+  constructor(
+    private provider: EthersProvider,
+    private intervalMs: number
+  ) {
+    super(FLOW_NAME);
+    this.metricClockSkew = new Gauge({
+      name: "watchdog_clock_skew_seconds",
+      help: "Local time minus the timestamp of the latest block in seconds (positive if the node is behind)",
+    });
+  }
+
+  public async run() {
+    while (true) {
+      const waitNextExecution = this.startCycle(this.intervalMs);
+
+      try {
+        this.metricRecorder.recordFlowStart();
+
+        await this.metricRecorder.stepExecution({
+          stepName: "get_block",
+          stepTimeoutMs: 10 * SEC,
+          fn: async () => {
+            const requestStart = Date.now();
+            const block = unwrap(await this.provider.getBlock("latest"));
+            // halfway through the request, so that the RPC round-trip doesn't count as skew
+            const requestTime = (requestStart + Date.now()) / 2;
+            const skew = requestTime / 1000 - block.timestamp;
+            this.logger.debug(`Block ${block.number} timestamp is ${skew.toFixed(1)} seconds behind the local clock`);
+            this.metricClockSkew.set(skew);
+            const exceeded = Math.abs(skew) > CLOCK_SKEW_TOLERANCE_SECONDS;
+            if (exceeded && (lastClockSkew == null || Math.abs(lastClockSkew) <= CLOCK_SKEW_TOLERANCE_SECONDS)) {
+              this.logger.warn(`Clock skew of ${skew.toFixed(1)} seconds exceeds ${CLOCK_SKEW_TOLERANCE_SECONDS}`);
+            }
+            metricClockSkewExceeded.set(exceeded ? 1 : 0);
+            lastClockSkew = skew;
+          },
+        });
+
+        this.metricRecorder.recordFlowSuccess();
+        // eslint-disable-next-line @typescript-eslint/no-explicit-any
+      } catch (error: any) {
+        this.logger.error("Clock skew error: " + error?.message, error?.stack);
+        this.metricRecorder.recordFlowFailure();
+      }
+
+      await waitNextExecution();
+    }
+  }
+}
@@ -0,0 +1,171 @@
+import { readFileSync } from "fs";
+
+/**
+ * Optional TOML configuration file, passed as `--config <path>`.
+ *
+ * All configuration is read from environment variables, so the file is flattened into them: table names and
+ * keys are joined with `_` and uppercased, so `[flow.transfer] interval = 60000` becomes
+ * `FLOW_TRANSFER_INTERVAL=60000`. Booleans map to `1`/`0` and arrays are joined with commas (e.g. for RPC URL
+ * lists). Variables already set in the environment (or in `.env`) take precedence over the file.
+ *
+ * Only the subset of TOML needed for that is supported: tables, bare and dotted keys, strings, numbers,
+ * booleans and single-line arrays of those. Anything else (arrays of tables, inline tables, multi-line strings and
+ * arrays, quoted keys, dates, ...) is rejected with an error, rather than being parsed into something unexpected.
+ */
+
+type TomlValue = string | number | boolean | TomlValue[];
+
+class ConfigFileError extends Error {
+  constructor(path: string, line: number, message: string) {
+    super(`${path}:${line}: ${message}`);
+    this.name = "ConfigFileError";
+  }
+}
+
+const BARE_KEY = /^[A-Za-z0-9_-]+(\s*\.\s*[A-Za-z0-9_-]+)*$/;
+const ESCAPES: Record<string, string> = { '"': '"', "\\": "\\", b: "\b", f: "\f", n: "\n", t: "\t", r: "\r" };
+/// number of hex digits of the `\u` and `\U` escapes
+const UNICODE_ESCAPES: Record<string, number> = { u: 4, U: 8 };
+
+const skipWhitespace = (src: string, pos: number): number => {
+  while (pos < src.length && (src[pos] === " " || src[pos] === "\t")) pos++;
+  return pos;
+};
+
+/** Parses the value starting at `pos`, returns it together with the position right after it. */
+const parseValue = (src: string, pos: number, fail: (message: string) => never): [TomlValue, number] => {
+  pos = skipWhitespace(src, pos);
+  const ch = src[pos];
+  if (src.startsWith('"""', pos) || src.startsWith("'''", pos)) fail("multi-line strings are not supported");
+  if (ch === "{") fail("inline tables are not supported");
+  if (ch === '"') {
+    let value = "";
+    for (pos++; pos < src.length && src[pos] !== '"'; pos++) {
+      if (src[pos] !== "\\") {
+        value += src[pos];
+      } else if (src[pos + 1] in UNICODE_ESCAPES) {
+        const digits = src.slice(pos + 2, pos + 2 + UNICODE_ESCAPES[src[pos + 1]]);
+        const codePoint = parseInt(digits, 16);
+        if (!/^[0-9A-Fa-f]+$/.test(digits) || digits.length !== UNICODE_ESCAPES[src[pos + 1]] || codePoint > 0x10ffff) {
+          fail(`invalid escape sequence \\${src[pos + 1]}${digits}`);
+        }
+        value += String.fromCodePoint(codePoint);
+        pos += 1 + digits.length;
+      } else if (src[pos + 1] in ESCAPES) {
+        value += ESCAPES[src[pos + 1]];
+        pos++;
+      } else {
+        fail(`unsupported escape sequence \\${src[pos + 1]}`);
+      }
+    }
+    if (pos >= src.length) fail("unterminated string");
+    return [value, pos + 1];
+  }
+  if (ch === "'") {
+    const end = src.indexOf("'", pos + 1);
+    if (end === -1) fail("unterminated string");
+    return [src.slice(pos + 1, end), end + 1];
+  }
+  if (ch === "[") {
+    const items: TomlValue[] = [];
+    pos = skipWhitespace(src, pos + 1);
+    while (src[pos] !== "]") {
+      if (pos >= src.length) fail("unterminated array (multi-line arrays are not supported)");
+      const [item, next] = parseValue(src, pos, fail);
+      items.push(item);
+      pos = skipWhitespace(src, next);
+      if (src[pos] === ",") {
+        pos = skipWhitespace(src, pos + 1);
+      } else if (src[pos] !== "]") {
+        fail("expected , or ] in array");
+      }
+    }
+    return [items, pos + 1];
+  }
+  const bare = /^[^\s,\]#]+/.exec(src.slice(pos))?.[0];
+  if (bare === "true" || bare === "false") return [bare === "true", pos + bare.length];
+  // no leading zeros, as in TOML (e.g. `0755` isn't octal)
+  if (bare != null && /^[+-]?(0|[1-9][0-9_]*)(\.[0-9_]+)?([eE][+-]?[0-9]+)?$/.test(bare)) {
+    return [Number(bare.replace(/_/g, "")), pos + bare.length];
+  }
+  return fail(`unsupported value ${bare ?? src.slice(pos)}`);
+};
+
+const toEnvValue = (value: TomlValue): string => {
+  if (Array.isArray(value)) return value.map(toEnvValue).join(",");
+  if (typeof value === "boolean") return value ? "1" : "0";
+  return String(value);
+};
+
+const toEnvName = (parts: string[]): string =>
+  parts
+    .join("_")
+    .replace(/[.-]/g, "_")
+    .replace(/\s/g, "")
+    .toUpperCase();
+
+/** Parses the TOML file into environment variable names and values. */
+export function parseConfigFile(path: string, content: string): Record<string, string> {
+  const env: Record<string, string> = {};
+  let table: string[] = [];
+  const tables = new Set<string>();
+  content.split(/\r?\n/).forEach((rawLine, index) => {
+    const fail = (message: string): never => {
+      throw new ConfigFileError(path, index + 1, message);
+    };
+    const line = rawLine.trim();
+    if (line === "" || line.startsWith("#")) return;
+
+    const header = /^\[\s*([^[\]]+?)\s*\]\s*(#.*)?$/.exec(line);
+    if (header != null) {
+      if (!BARE_KEY.test(header[1])) fail(`unsupported table name ${header[1]}`);
+      if (tables.has(toEnvName([header[1]]))) fail(`duplicate table ${header[1]}`);
+      tables.add(toEnvName([header[1]]));
+      table = [header[1]];
+      return;
+    }
+    if (line.startsWith("[")) fail("arrays of tables are not supported");
+
+    const eq = line.indexOf("=");
+    if (eq === -1) fail("expected key = value");
+    const key = line.slice(0, eq).trim();
+    if (!BARE_KEY.test(key)) fail(`unsupported key ${key}`);
+    const [value, end] = parseValue(line, eq + 1, fail);
+    const rest = line.slice(end).trim();
+    if (rest !== "" && !rest.startsWith("#")) fail(`unexpected ${rest} after value`);
+
+    const name = toEnvName([...table, key]);
+    if (name in env) fail(`duplicate key ${name}`);
+    env[name] = toEnvValue(value);
+  });
+  return env;
+}
+
+/** Returns the `--config <path>` (or `--config=<path>`) command line argument, if any. */
+export function getConfigFileArg(argv: string[]): string | undefined {
+  for (let i = 0; i < argv.length; i++) {
+    if (argv[i] === "--config") {
+      if (argv[i + 1] == null) throw new Error("--config requires a path");
+      return argv[i + 1];
+    }
+    if (argv[i].startsWith("--config=")) return argv[i].slice("--config=".length);
+  }
+  return undefined;
+}
+
+/** Fills environment variables that are not set yet from the config file. */
+export function loadConfigFile(path: string) {
+  const env = parseConfigFile(path, readFileSync(path, "utf8"));
+  for (const [name, value] of Object.entries(env)) {
+    if (process.env[name] === undefined) {
+      process.env[name] = value;
+    }
+  }
+}
+
+// Flows read their configuration when their modules are loaded, so this module has to be imported (and
+// the file applied) before any of them.
+const configFile = getConfigFileArg(process.argv.slice(2));
+if (configFile != null) {
+  loadConfigFile(configFile);
+}
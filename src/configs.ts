@@ -1,4 +1,55 @@
 import { MIN, SEC } from "./utils";
 
+/// deployment tier (e.g. `dev`, `staging`, `prod`), to tell watchdogs of the same chain apart in shared monitoring
+export const ENVIRONMENT = process.env.ENVIRONMENT || null;
 export const L2_EXECUTION_TIMEOUT = +(process.env.L2_EXECUTION_TIMEOUT ?? 15 * SEC);
+/// inclusion deadline of the transfer for SLA purposes, replaces `L2_EXECUTION_TIMEOUT` as its receipt timeout
+export const MAX_MEMPOOL_WAIT_SECONDS = process.env.MAX_MEMPOOL_WAIT_SECONDS
+  ? +process.env.MAX_MEMPOOL_WAIT_SECONDS
+  : null;
+/// `fast` accepts the first inclusion of the transfer, `safe` additionally waits until its block is finalized
+export const CONFIRMATION_MODE = process.env.CONFIRMATION_MODE ?? "fast";
+if (CONFIRMATION_MODE !== "fast" && CONFIRMATION_MODE !== "safe") {
+  throw new Error(`Invalid CONFIRMATION_MODE ${CONFIRMATION_MODE}, expected "fast" or "safe"`);
+}
+/// a reverted transfer exits the process, for strict environments where it means something is fundamentally broken
+export const EXIT_ON_REVERT = process.env.EXIT_ON_REVERT === "1";
+/// block tag the transfer is re-checked against in `safe` mode, `latest` for chains without finality
+export const CONFIRMATION_BLOCK_TAG = process.env.CONFIRMATION_BLOCK_TAG ?? "finalized";
+if (!["latest", "safe", "finalized"].includes(CONFIRMATION_BLOCK_TAG)) {
+  throw new Error(`Invalid CONFIRMATION_BLOCK_TAG ${CONFIRMATION_BLOCK_TAG}, expected "latest", "safe" or "finalized"`);
+}
+export const FINALIZATION_TIMEOUT = +(process.env.FINALIZATION_TIMEOUT ?? 3 * 60 * MIN);
+/// `fixed_rate` starts flow cycles every interval, `fixed_delay` waits the interval after each cycle
+export const SCHEDULE_MODE = process.env.SCHEDULE_MODE ?? "fixed_delay";
+if (SCHEDULE_MODE !== "fixed_rate" && SCHEDULE_MODE !== "fixed_delay") {
+  throw new Error(`Invalid SCHEDULE_MODE ${SCHEDULE_MODE}, expected "fixed_rate" or "fixed_delay"`);
+}
 export const SETTLEMENT_DEADLINE = +(process.env.SETTLEMENT_DEADLINE ?? 90 * MIN);
+export const STARTUP_DELAY_SECONDS = +(process.env.STARTUP_DELAY_SECONDS ?? 0);
+export const STARTUP_WAIT_FOR_SYNC = process.env.STARTUP_WAIT_FOR_SYNC === "1";
+/// resolve the static chain values before starting the flows, so that their first cycle is representative
+export const STARTUP_WARMUP = process.env.STARTUP_WARMUP === "1";
+/// pin the fees of transfer and deposit transactions instead of estimating them (e.g. for controlled load testing)
+export const OVERRIDE_MAX_FEE_WEI = process.env.OVERRIDE_MAX_FEE_WEI ? BigInt(process.env.OVERRIDE_MAX_FEE_WEI) : null;
+export const OVERRIDE_PRIORITY_FEE_WEI = process.env.OVERRIDE_PRIORITY_FEE_WEI
+  ? BigInt(process.env.OVERRIDE_PRIORITY_FEE_WEI)
+  : null;
+/// floor for the estimated priority fee, some nodes quote a zero tip that leaves the transaction pending
+export const MIN_PRIORITY_FEE_WEI = BigInt(process.env.MIN_PRIORITY_FEE_WEI ?? 0);
+/// only used to format logged base token amounts, metrics are in raw base units
+export const BASE_TOKEN_DECIMALS = +(process.env.BASE_TOKEN_DECIMALS ?? 18);
+/// gas limit of the transfer transactions instead of estimating it every cycle, for slow or rate limited endpoints
+export const FIXED_GAS_LIMIT = process.env.FIXED_GAS_LIMIT ? BigInt(process.env.FIXED_GAS_LIMIT) : null;
+/// sleep between estimating and sending transfer transactions, to probe how the node handles stale estimates
+export const ESTIMATE_SEND_DELAY_MS = +(process.env.ESTIMATE_SEND_DELAY_MS ?? 0);
+/// period of the estimate-only transfer checks, which run in between the (costly) full sends of the transfer flow
+export const ESTIMATE_PERIOD_SECONDS = process.env.ESTIMATE_PERIOD_SECONDS
+  ? +process.env.ESTIMATE_PERIOD_SECONDS
+  : null;
+/// block tag (`latest`, `pending`, ...) or number the transfer gas is estimated at, instead of the node default
+export const ESTIMATE_BLOCK_TAG = process.env.ESTIMATE_BLOCK_TAG
+  ? /^\d+$/.test(process.env.ESTIMATE_BLOCK_TAG)
+    ? `0x${BigInt(process.env.ESTIMATE_BLOCK_TAG).toString(16)}`
+    : process.env.ESTIMATE_BLOCK_TAG
+  : null;
@@ -1,26 +1,37 @@
 import "dotenv/config";
 
-import { formatEther, MaxInt256, parseEther, toBigInt } from "ethers";
+import { formatEther, formatUnits, MaxInt256, parseEther, toBigInt } from "ethers";
+import { Gauge } from "prom-client";
 import { utils } from "zksync-ethers";
 import { ETH_ADDRESS_IN_CONTRACTS } from "zksync-ethers/build/utils";
 
+import { BASE_TOKEN_DECIMALS } from "./configs";
 import {
+  ABI_MISMATCH_HINT,
+  DEPOSIT_L1_CONFIRMATIONS,
   DEPOSIT_L1_GAS_PRICE_LIMIT_GWEI,
   DEPOSIT_RETRY_INTERVAL,
   DEPOSIT_RETRY_LIMIT,
   DepositBaseFlow,
+  isAbiMismatch,
   PRIORITY_OP_TIMEOUT,
   STEPS,
 } from "./depositBase";
+import { applyPriorityFeeFloor, getFeeOverrides } from "./fees";
 import { recordL1Balances, Status } from "./flowMetric";
+import { checkChainIdUnchanged } from "./rpcLoggingProvider";
+import { isSpendBudgetExhausted } from "./spendBudget";
+import { pendingOperations } from "./stateStore";
 import { SEC, MIN, unwrap, timeoutPromise } from "./utils";
 
+import type { PendingOperation } from "./stateStore";
 import type { BigNumberish, BytesLike, Overrides, Provider as EthersProvider } from "ethers";
 import type { Wallet } from "zksync-ethers";
 import type { IL1SharedBridge } from "zksync-ethers/build/typechain";
 import type { Address } from "zksync-ethers/build/types";
 
 const FLOW_NAME = "deposit";
+const OPERATION_TYPE = "deposit";
 type L2Request = {
   contractAddress: Address;
   calldata: string;
@@ -33,8 +44,15 @@ type L2Request = {
   refundRecipient?: Address;
   overrides?: Overrides;
 };
+type PopulatedL2Request = L2Request & {
+  overrides: Overrides & { gasLimit: bigint; nonce: number; maxFeePerGas: bigint };
+};
 
 export class DepositFlow extends DepositBaseFlow {
+  private metricInsufficientL1Funds: Gauge;
+  private metricL1MaxFeePerGas: Gauge;
+  private metricL1BaseFee: Gauge;
+
   constructor(
     wallet: Wallet,
     sharedBridge: IL1SharedBridge,
@@ -46,66 +64,180 @@ export class DepositFlow extends DepositBaseFlow {
     private intervalMs: number
   ) {
     super(wallet, sharedBridge, zkChainAddress, chainId, baseToken, l2EthersProvider, isZKsyncOS, FLOW_NAME);
+    this.metricInsufficientL1Funds = new Gauge({
+      name: "watchdog_deposit_insufficient_l1_funds",
+      help: "1 if the last deposit was skipped because the L1 balance doesn't cover its value and L1 gas, 0 otherwise",
+    });
+    this.metricL1MaxFeePerGas = new Gauge({
+      name: "watchdog_l1_max_fee_per_gas",
+      help: "Max fee per gas (in wei) of the last deposit estimation on L1",
+    });
+    this.metricL1BaseFee = new Gauge({
+      name: "watchdog_l1_base_fee",
+      help: "L1 base fee per gas (in wei) of the latest block at the last deposit estimation",
+    });
+  }
+
+  private async ensureBaseTokenApproval() {
+    if (this.baseToken != ETH_ADDRESS_IN_CONTRACTS) {
+      const bridgeAddress = await this.sharedBridge.getAddress();
+      const allowance = await this.wallet.getAllowanceL1(this.baseToken, bridgeAddress);
+
+      // heuristic condition to determine if we should perform the infinite approval
+      if (allowance < parseEther("100000")) {
+        this.logger.info(`Approving base token ${this.baseToken} for infinite amount`);
+        let overrides = {};
+        if (this.isZKsyncOS) {
+          overrides = {
+            bridgeAddress,
+          };
+        }
+        await this.wallet.approveERC20(this.baseToken, MaxInt256, overrides);
+      } else {
+        this.logger.info(`Base token ${this.baseToken} already has approval`);
+      }
+      const baseTokenBalance = await this.wallet.getBalanceL1(this.baseToken);
+      const l1EthBalance = await this.wallet._providerL1().getBalance(this.wallet.address);
+      this.logger.info(
+        `L1 balance: Base token (${this.baseToken}) ${formatUnits(baseTokenBalance, BASE_TOKEN_DECIMALS)}; ETH: ${formatEther(l1EthBalance.toString())}`
+      );
+      recordL1Balances(baseTokenBalance, l1EthBalance);
+    }
+  }
+
+  private async estimateDeposit(): Promise<PopulatedL2Request> {
+    const populatedWithOverrides = await this.metricRecorder.estimationStepExecution({
+      stepName: STEPS.estimation,
+      stepTimeoutMs: 30 * SEC,
+      fn: async ({ recordStepGas, recordStepGasCost, recordStepGasPrice }) => {
+        const request = this.getDepositRequest();
+        const populated: L2Request = await this.wallet.getDepositTx({
+          ...request,
+          overrides: { ...request.overrides, ...getFeeOverrides() },
+        });
+        const floored = applyPriorityFeeFloor(populated.overrides ?? {});
+        if (floored != null) {
+          const estimated = populated.overrides?.maxPriorityFeePerGas;
+          this.logger.debug(`Estimated priority fee ${estimated} raised to the floor`);
+          this.metricRecorder.recordPriorityFeeFloorApplied();
+          populated.overrides = floored;
+        }
+        const maxFeePerGas = toBigInt(unwrap(populated.overrides?.maxFeePerGas)); // we expect the library to populate this field as we are post EIP-1559
+        const estimatedGas = await this.wallet.estimateGasRequestExecute(populated);
+        const nonce = await this.wallet._signerL1().getNonce("latest");
+        const latestL1Block = await this.wallet._providerL1().getBlock("latest");
+        this.metricL1MaxFeePerGas.set(Number(maxFeePerGas));
+        if (latestL1Block?.baseFeePerGas != null) {
+          this.metricL1BaseFee.set(Number(latestL1Block.baseFeePerGas));
+        }
+        recordStepGas(estimatedGas);
+        recordStepGasPrice(maxFeePerGas);
+        recordStepGasCost(estimatedGas * maxFeePerGas);
+        return {
+          ...populated,
+          overrides: {
+            ...populated.overrides,
+            gasLimit: estimatedGas,
+            nonce,
+            maxFeePerGas,
+          },
+        };
+      },
+    });
+    // record l2 estimates using the manual record function
+    this.metricRecorder.manualRecordStepGas(STEPS.l2_estimation, unwrap(populatedWithOverrides.l2GasLimit));
+    this.metricRecorder.manualRecordStepGasCost(
+      STEPS.l2_estimation,
+      BigInt(unwrap(populatedWithOverrides.mintValue)) - BigInt(unwrap(populatedWithOverrides.l2Value))
+    );
+    return populatedWithOverrides;
+  }
+
+  private async sendDeposit(populatedWithOverrides: PopulatedL2Request) {
+    const { l2Value, mintValue } = populatedWithOverrides;
+    this.logger.info(
+      `Depositing ${formatUnits(l2Value ?? 0, BASE_TOKEN_DECIMALS)} base token (minting ${formatUnits(mintValue ?? 0, BASE_TOKEN_DECIMALS)} on L2)`
+    );
+    const depositHandle = await this.metricRecorder.stepExecution({
+      stepName: STEPS.send,
+      stepTimeoutMs: 30 * SEC,
+      fn: () => this.wallet.requestExecute(populatedWithOverrides),
+    });
+    this.logger.info(`Tx (L1: ${depositHandle.hash}) sent on L1`);
+    return depositHandle;
+  }
+
+  private async waitL1Execution(l1TxHash: string) {
+    // NOTE: bypasses the deposit handle's waitL1Commit — it calls
+    // provider.waitForTransaction WITHOUT a timeout, so on step timeout the
+    // poller would leak. Using ethers' native timeout cleans up the subscriber.
+    return await this.metricRecorder.stepExecution({
+      stepName: STEPS.l1_execution,
+      stepTimeoutMs: 3 * MIN,
+      fn: async ({ recordStepGas, recordStepGasPrice, recordStepGasCost, timeoutMs }) => {
+        const txReceipt = unwrap(await this.wallet._providerL1().waitForTransaction(l1TxHash, 1, timeoutMs));
+        recordStepGas(unwrap(txReceipt?.gasUsed));
+        recordStepGasPrice(unwrap(txReceipt?.gasPrice));
+        const cost = unwrap(txReceipt?.gasUsed) * unwrap(txReceipt?.gasPrice);
+        recordStepGasCost(cost);
+        this.metricRecorder.recordTxCost(cost);
+        return txReceipt;
+      },
+    }); // included in a block on L1
+  }
+
+  /**
+   * Sends a single deposit and waits for its L1 receipt, through the same steps as the deposit loop. Used by the
+   * `deposit-once` command to verify the bridge configuration manually.
+   */
+  public async depositOnce(): Promise<{ l1TxHash: string; baseCost: bigint; l2GasLimit: bigint }> {
+    await this.ensureBaseTokenApproval();
+    const populatedWithOverrides = await this.estimateDeposit();
+    const depositHandle = await this.sendDeposit(populatedWithOverrides);
+    await this.waitL1Execution(depositHandle.hash);
+    return {
+      l1TxHash: depositHandle.hash,
+      baseCost: BigInt(unwrap(populatedWithOverrides.mintValue)) - BigInt(unwrap(populatedWithOverrides.l2Value)),
+      l2GasLimit: BigInt(unwrap(populatedWithOverrides.l2GasLimit)),
+    };
+  }
+
+  /**
+   * Performs all the estimation of a deposit (`zks_estimateGasL1ToL2`, `l2TransactionBaseCost`, L1 gas) without
+   * approving or sending anything. Used by `deposit-once --dry-run` to validate the deposit math.
+   */
+  public async estimateDepositOnce(): Promise<{
+    value: bigint;
+    baseCost: bigint;
+    l2GasLimit: bigint;
+    l1GasLimit: bigint;
+    l1MaxFeePerGas: bigint;
+  }> {
+    const populatedWithOverrides = await this.estimateDeposit();
+    return {
+      value: BigInt(populatedWithOverrides.overrides.value ?? 0),
+      baseCost: BigInt(unwrap(populatedWithOverrides.mintValue)) - BigInt(unwrap(populatedWithOverrides.l2Value)),
+      l2GasLimit: BigInt(unwrap(populatedWithOverrides.l2GasLimit)),
+      l1GasLimit: populatedWithOverrides.overrides.gasLimit,
+      l1MaxFeePerGas: populatedWithOverrides.overrides.maxFeePerGas,
+    };
   }
 
   protected async executeWatchdogDeposit(): Promise<Status> {
+    let pendingTxHash: string | undefined;
     try {
       // even before flow start we check base token allowance and perform an unlimited approval if needed
-      if (this.baseToken != ETH_ADDRESS_IN_CONTRACTS) {
-        const bridgeAddress = await this.sharedBridge.getAddress();
-        const allowance = await this.wallet.getAllowanceL1(this.baseToken, bridgeAddress);
-
-        // heuristic condition to determine if we should perform the infinite approval
-        if (allowance < parseEther("100000")) {
-          this.logger.info(`Approving base token ${this.baseToken} for infinite amount`);
-          let overrides = {};
-          if (this.isZKsyncOS) {
-            overrides = {
-              bridgeAddress,
-            };
-          }
-          await this.wallet.approveERC20(this.baseToken, MaxInt256, overrides);
-        } else {
-          this.logger.info(`Base token ${this.baseToken} already has approval`);
-        }
-        const baseTokenBalance = await this.wallet.getBalanceL1(this.baseToken);
-        const l1EthBalance = await this.wallet._providerL1().getBalance(this.wallet.address);
-        this.logger.info(
-          `L1 balance: Base token (${this.baseToken}) ${formatEther(baseTokenBalance.toString())}; ETH: ${formatEther(l1EthBalance.toString())}`
-        );
-        recordL1Balances(baseTokenBalance, l1EthBalance);
-      }
+      await this.ensureBaseTokenApproval();
 
       this.metricRecorder.recordFlowStart();
 
-      const populatedWithOverrides = await this.metricRecorder.stepExecution({
-        stepName: STEPS.estimation,
-        stepTimeoutMs: 30 * SEC,
-        fn: async ({ recordStepGas, recordStepGasCost, recordStepGasPrice }) => {
-          const populated: L2Request = await this.wallet.getDepositTx(this.getDepositRequest());
-          const maxFeePerGas = toBigInt(unwrap(populated.overrides?.maxFeePerGas)); // we expect the library to populate this field as we are post EIP-1559
-          const estimatedGas = await this.wallet.estimateGasRequestExecute(populated);
-          const nonce = await this.wallet._signerL1().getNonce("latest");
-          recordStepGas(estimatedGas);
-          recordStepGasPrice(maxFeePerGas);
-          recordStepGasCost(estimatedGas * maxFeePerGas);
-          return {
-            ...populated,
-            overrides: {
-              ...populated.overrides,
-              gasLimit: estimatedGas,
-              nonce,
-              maxFeePerGas,
-            },
-          };
-        },
-      });
-      // record l2 estimates using the manual record function
-      this.metricRecorder.manualRecordStepGas(STEPS.l2_estimation, unwrap(populatedWithOverrides.l2GasLimit));
-      this.metricRecorder.manualRecordStepGasCost(
-        STEPS.l2_estimation,
-        BigInt(unwrap(populatedWithOverrides.mintValue)) - BigInt(unwrap(populatedWithOverrides.l2Value))
-      );
+      if (isSpendBudgetExhausted()) {
+        this.logger.warn("Spend budget exhausted. Skipping deposit");
+        this.metricRecorder.recordFlowSkipped();
+        return Status.SKIP;
+      }
+
+      const populatedWithOverrides = await this.estimateDeposit();
       if (populatedWithOverrides.overrides.maxFeePerGas > DEPOSIT_L1_GAS_PRICE_LIMIT_GWEI) {
         this.logger.warn(
           `Gas price ${populatedWithOverrides.overrides.maxFeePerGas} is higher than limit ${DEPOSIT_L1_GAS_PRICE_LIMIT_GWEI}. Skipping deposit`
@@ -114,59 +246,100 @@ export class DepositFlow extends DepositBaseFlow {
         return Status.SKIP;
       }
 
+      // the tx value covers the L2 base cost and the deposited amount (for ETH based chains), plus the L1 gas
+      const { value, gasLimit, maxFeePerGas } = populatedWithOverrides.overrides;
+      const requiredL1Funds = BigInt(value ?? 0) + gasLimit * maxFeePerGas;
+      const l1Balance = await this.wallet._providerL1().getBalance(this.wallet.address);
+      const insufficientL1Funds = l1Balance < requiredL1Funds;
+      this.metricInsufficientL1Funds.set(insufficientL1Funds ? 1 : 0);
+      if (insufficientL1Funds) {
+        this.logger.error(
+          `L1 balance ${formatEther(l1Balance)} ETH is lower than the ${formatEther(requiredL1Funds)} ETH required for the deposit. Skipping deposit`
+        );
+        this.metricRecorder.recordFlowSkipped();
+        return Status.SKIP;
+      }
+
       // send L1 deposit transaction
-      const depositHandle = await this.metricRecorder.stepExecution({
-        stepName: STEPS.send,
-        stepTimeoutMs: 30 * SEC,
-        fn: () => this.wallet.requestExecute(populatedWithOverrides),
-      });
-      this.logger.info(`Tx (L1: ${depositHandle.hash}) sent on L1`);
+      const depositHandle = await this.sendDeposit(populatedWithOverrides);
+      // persisted until the deposit is executed on L2, so that a restart keeps tracking it
+      pendingTxHash = depositHandle.hash;
+      pendingOperations.add({ type: OPERATION_TYPE, txHash: pendingTxHash, startedAt: Date.now() });
 
       // wait for transaction
-      // NOTE: bypasses depositHandle.waitL1Commit — it calls
-      // provider.waitForTransaction WITHOUT a timeout, so on step timeout the
-      // poller would leak. Using ethers' native timeout cleans up the subscriber.
-      const txReceipt = await this.metricRecorder.stepExecution({
-        stepName: STEPS.l1_execution,
-        stepTimeoutMs: 3 * MIN,
-        fn: async ({ recordStepGas, recordStepGasPrice, recordStepGasCost, timeoutMs }) => {
-          const txReceipt = unwrap(
-            await this.wallet._providerL1().waitForTransaction(depositHandle.hash, 1, timeoutMs)
-          );
-          recordStepGas(unwrap(txReceipt?.gasUsed));
-          recordStepGasPrice(unwrap(txReceipt?.gasPrice));
-          recordStepGasCost(unwrap(txReceipt?.gasUsed) * unwrap(txReceipt?.gasPrice));
-          return txReceipt;
-        },
-      }); // included in a block on L1
+      const txReceipt = await this.waitL1Execution(depositHandle.hash);
 
       const l2TxHash = utils.getL2HashFromPriorityOp(txReceipt, this.zkChainAddress);
       const txHashs = `(L1: ${depositHandle.hash}, L2: ${l2TxHash})`;
       this.logger.info(`Tx ${txHashs} mined on l1`);
+      // both start from the L1 inclusion, so that the L1 finality cost and the L2 execution are measured independently
+      const l1Confirmations = async () => {
+        if (DEPOSIT_L1_CONFIRMATIONS <= 1) return;
+        await this.metricRecorder.stepExecution({
+          stepName: STEPS.l1_confirmations,
+          stepTimeoutMs: 3 * MIN + DEPOSIT_L1_CONFIRMATIONS * 15 * SEC,
+          fn: async ({ timeoutMs }) => {
+            const l1Provider = this.wallet._providerL1();
+            unwrap(await l1Provider.waitForTransaction(depositHandle.hash, DEPOSIT_L1_CONFIRMATIONS, timeoutMs));
+          },
+        });
+        this.logger.info(`Tx ${txHashs} confirmed by ${DEPOSIT_L1_CONFIRMATIONS} L1 blocks`);
+      };
       // wait for deposit to be finalized
-      await this.metricRecorder.stepExecution({
-        stepName: STEPS.l2_execution,
-        stepTimeoutMs: PRIORITY_OP_TIMEOUT,
-        fn: async ({ recordStepGasPrice, recordStepGas, recordStepGasCost }) => {
-          const receipt = unwrap(await this.l2EthersProvider.waitForTransaction(l2TxHash, 1, PRIORITY_OP_TIMEOUT));
-          recordStepGasPrice(unwrap(receipt.gasPrice));
-          recordStepGas(unwrap(receipt.gasUsed));
-          recordStepGasCost(unwrap(receipt.gasUsed) * unwrap(receipt.gasPrice));
-        },
-      });
-      this.logger.info(`Tx ${txHashs} mined on L2`);
+      const l2Execution = async () => {
+        await this.metricRecorder.stepExecution({
+          stepName: STEPS.l2_execution,
+          stepTimeoutMs: PRIORITY_OP_TIMEOUT,
+          fn: async ({ recordStepGasPrice, recordStepGas, recordStepGasCost }) => {
+            const receipt = unwrap(await this.l2EthersProvider.waitForTransaction(l2TxHash, 1, PRIORITY_OP_TIMEOUT));
+            recordStepGasPrice(unwrap(receipt.gasPrice));
+            recordStepGas(unwrap(receipt.gasUsed));
+            recordStepGasCost(unwrap(receipt.gasUsed) * unwrap(receipt.gasPrice));
+          },
+        });
+        this.logger.info(`Tx ${txHashs} mined on L2`);
+      };
+      await Promise.all([l1Confirmations(), l2Execution()]);
 
       this.metricRecorder.recordFlowSuccess();
       return Status.OK;
       // eslint-disable-next-line @typescript-eslint/no-explicit-any
     } catch (error: any) {
       this.logger.error("deposit tx error: " + error?.message, error?.stack);
+      if (isAbiMismatch(error)) this.logger.error(ABI_MISMATCH_HINT);
       this.metricRecorder.recordFlowFailure();
       return Status.FAIL;
+    } finally {
+      if (pendingTxHash != null) pendingOperations.remove(pendingTxHash);
+    }
+  }
+
+  /** Waits for a deposit sent before a restart to be executed on L2 and records its end-to-end latency. */
+  private async resumePendingDeposit(operation: PendingOperation) {
+    const txHashs = `(L1: ${operation.txHash})`;
+    this.logger.info(`Resuming tracking of deposit ${txHashs} sent before restart`);
+    try {
+      const l1Receipt = unwrap(
+        await this.wallet._providerL1().waitForTransaction(operation.txHash, 1, PRIORITY_OP_TIMEOUT)
+      );
+      const l2TxHash = utils.getL2HashFromPriorityOp(l1Receipt, this.zkChainAddress);
+      unwrap(await this.l2EthersProvider.waitForTransaction(l2TxHash, 1, PRIORITY_OP_TIMEOUT));
+      const latencySec = (Date.now() - operation.startedAt) / 1000;
+      this.logger.info(`Tx ${txHashs} mined on L2 as ${l2TxHash} after ${latencySec.toFixed(0)} seconds`);
+      this.metricRecorder.manualRecordStatus(Status.OK, latencySec);
+      // eslint-disable-next-line @typescript-eslint/no-explicit-any
+    } catch (error: any) {
+      this.logger.error(`resumed deposit ${txHashs} error: ${error?.message}`, error?.stack);
+      this.metricRecorder.manualRecordStatus(Status.FAIL, 0);
+    } finally {
+      pendingOperations.remove(operation.txHash);
     }
   }
 
   public async run() {
+    for (const operation of pendingOperations.list(OPERATION_TYPE)) {
+      await this.resumePendingDeposit(operation);
+    }
     const lastExecution = await this.getLastExecution(this.wallet.address);
     const currentBlockchainTimestamp = await this.getCurrentChainTimestamp();
     const timeSinceLastDepositSec = currentBlockchainTimestamp - lastExecution.timestampL1;
@@ -177,7 +350,8 @@ export class DepositFlow extends DepositBaseFlow {
       await timeoutPromise(waitTime);
     }
     while (true) {
-      const nextExecutionWait = timeoutPromise(this.intervalMs);
+      const waitNextExecution = this.startCycle(this.intervalMs);
+      await checkChainIdUnchanged(this.wallet._providerL1());
       let attempt: number = 1;
       while (attempt <= DEPOSIT_RETRY_LIMIT) {
         const result = await this.executeWatchdogDeposit();
@@ -187,6 +361,8 @@ export class DepositFlow extends DepositBaseFlow {
             break;
           case Status.SKIP:
             this.logger.info(`attempt ${attempt} skipped (not counted towards limit)`);
+            // the skip conditions (gas price, L1 funds, spend budget) don't clear up immediately
+            await timeoutPromise(DEPOSIT_RETRY_INTERVAL);
             break;
           case Status.FAIL: {
             attempt++;
@@ -206,7 +382,7 @@ export class DepositFlow extends DepositBaseFlow {
         }
         if (result == Status.OK) break;
       }
-      await nextExecutionWait;
+      await waitNextExecution();
     }
   }
 }
@@ -1,5 +1,6 @@
 import "dotenv/config";
-import { id } from "ethers";
+import { Contract, id, isAddress } from "ethers";
+import { readFileSync } from "fs";
 import { utils } from "zksync-ethers";
 
 import { BaseFlow } from "./baseFlow";
@@ -8,7 +9,7 @@ import { MIN, SEC, unwrap } from "./utils";
 
 import type { BigNumberish, BytesLike, Overrides, TransactionReceipt, Provider as EthersProvider } from "ethers";
 import type { types, Wallet } from "zksync-ethers";
-import type { IL1SharedBridge } from "zksync-ethers/build/typechain";
+import type { IBridgehub, IL1SharedBridge } from "zksync-ethers/build/typechain";
 
 type DepositTxRequest = {
   token: types.Address;
@@ -42,6 +43,7 @@ export const STEPS = {
   estimation: "estimation",
   send: "send",
   l1_execution: "l1_execution",
+  l1_confirmations: "l1_confirmations",
   l2_estimation: "l2_estimation", //dummy step, no actual execution time reported
   l2_execution: "l2_execution",
 };
@@ -49,6 +51,44 @@ export const STEPS = {
 export const PRIORITY_OP_TIMEOUT = +(process.env.FLOW_DEPOSIT_L2_TIMEOUT ?? 15 * MIN);
 export const DEPOSIT_RETRY_INTERVAL = +(process.env.FLOW_DEPOSIT_RETRY_INTERVAL ?? 30 * SEC);
 export const DEPOSIT_RETRY_LIMIT = +(process.env.FLOW_DEPOSIT_RETRY_LIMIT ?? 3);
+/// L1 reorgs are more likely than L2 ones, so the deposit is only considered L1-confirmed after this many blocks
+export const DEPOSIT_L1_CONFIRMATIONS = +(
+  process.env.DEPOSIT_L1_CONFIRMATIONS ??
+  process.env.FLOW_DEPOSIT_L1_CONFIRMATIONS ??
+  3
+);
+/// L2 recipient of the deposits (e.g. a treasury), the depositing wallet itself if unset
+const DEPOSIT_L2_RECIPIENT = process.env.DEPOSIT_L2_RECIPIENT || null;
+if (DEPOSIT_L2_RECIPIENT != null && !isAddress(DEPOSIT_L2_RECIPIENT)) {
+  throw new Error(`Invalid DEPOSIT_L2_RECIPIENT ${DEPOSIT_L2_RECIPIENT}, expected an address`);
+}
+
+/// JSON ABI (or compiler artifact) of the Bridgehub, replacing the one bundled with zksync-ethers for other versions
+const DEPOSIT_BRIDGEHUB_ABI_PATH = process.env.DEPOSIT_BRIDGEHUB_ABI_PATH || null;
+
+/**
+ * Makes the wallet use the Bridgehub ABI of `DEPOSIT_BRIDGEHUB_ABI_PATH` for all its Bridgehub calls (contract
+ * resolution, `l2TransactionBaseCost`, deposits), so that the deposit flows can target a Bridgehub version that
+ * the ABI bundled with zksync-ethers doesn't match. No-op if unset.
+ */
+export function applyBridgehubAbi(wallet: Wallet) {
+  if (DEPOSIT_BRIDGEHUB_ABI_PATH == null) return;
+  const file = JSON.parse(readFileSync(DEPOSIT_BRIDGEHUB_ABI_PATH, "utf8"));
+  const abi = Array.isArray(file) ? file : file.abi;
+  wallet.getBridgehubContract = async () => {
+    const address = await wallet._providerL2().getBridgehubContractAddress();
+    return new Contract(address, abi, wallet._signerL1()) as unknown as IBridgehub;
+  };
+}
+
+/** Whether the error looks like the Bridgehub ABI in use doesn't match the deployed contract. */
+// eslint-disable-next-line @typescript-eslint/no-explicit-any
+export const isAbiMismatch = (error: any): boolean =>
+  error?.code === "BAD_DATA" || /could not decode result data|no matching fragment/i.test(error?.message ?? "");
+
+/// points at the option when a Bridgehub call fails to decode
+export const ABI_MISMATCH_HINT =
+  "The Bridgehub ABI doesn't seem to match the deployed contract, set DEPOSIT_BRIDGEHUB_ABI_PATH to its ABI";
 
 const GWEI = 1000n * 1000n * 1000n;
 /// We avoid L1 transactions if gas price is higher than this limit
@@ -71,7 +111,8 @@ export abstract class DepositBaseFlow extends BaseFlow {
 
   protected getDepositRequest(): DepositTxRequest {
     const request: DepositTxRequest = {
-      to: this.wallet.address,
+      // the L2 gas is estimated for this recipient as well
+      to: DEPOSIT_L2_RECIPIENT ?? this.wallet.address,
       token: this.baseToken,
       amount: 1, // just 1 wei
       refundRecipient: this.wallet.address,
@@ -10,6 +10,8 @@ import {
   STEPS,
 } from "./depositBase";
 import { Status } from "./flowMetric";
+import { checkChainIdUnchanged } from "./rpcLoggingProvider";
+import { isSpendBudgetExhausted } from "./spendBudget";
 import { SEC, timeoutPromise, unwrap } from "./utils";
 
 import type { ExecutionResultKnown } from "./depositBase";
@@ -92,6 +94,11 @@ export class DepositUserFlow extends DepositBaseFlow {
         this.metricRecorder.manualRecordStatus(Status.SKIP, 0);
         return Status.SKIP;
       }
+      if (isSpendBudgetExhausted()) {
+        this.logger.warn("Spend budget exhausted, skipping watchdog deposit");
+        this.metricRecorder.manualRecordStatus(Status.SKIP, 0);
+        return Status.SKIP;
+      }
       const depositHandle = await this.wallet.deposit({
         ...this.getDepositRequest(),
         overrides: {
@@ -101,10 +108,7 @@ export class DepositUserFlow extends DepositBaseFlow {
       });
       this.logger.info(`Deposit transaction sent ${depositHandle.hash}`);
       const txReceipt = await depositHandle.waitL1Commit(1);
-      const l2TxHash = utils.getL2HashFromPriorityOp(
-        txReceipt,
-        await this.wallet._providerL2().getMainContractAddress()
-      );
+      const l2TxHash = utils.getL2HashFromPriorityOp(txReceipt, this.zkChainAddress);
       this.logger.info(`Deposit transaction mined on L1, expecting L2 hash: ${l2TxHash}`);
       await depositHandle.wait(1);
       this.logger.info("Deposit transaction mined on L2. Checking status...");
@@ -124,7 +128,7 @@ export class DepositUserFlow extends DepositBaseFlow {
 
   public async run() {
     while (true) {
-      const nextExecutionWait = timeoutPromise(this.intervalMs);
+      const waitNextExecution = this.startCycle(this.intervalMs);
       const currentBlockchainTimestamp = await this.getCurrentChainTimestamp();
       const someDepositResult = await this.getLastExecution(void 0);
       // we only report OK. On fail we want perform a deposit manually as we cannot rely on users doing deposits properly
@@ -149,6 +153,7 @@ export class DepositUserFlow extends DepositBaseFlow {
           currentBlockchainTimestamp - Math.max(lastOurExecution.timestampL1, this.lastOnChainOperationTimestamp);
         if (timeSinceLastOurDeposit * SEC > this.txTriggerDelayMs) {
           this.logger.info("Starting manual deposit transaction");
+          await checkChainIdUnchanged(this.wallet._providerL1());
           let attempt = 0;
           while (attempt < DEPOSIT_RETRY_LIMIT) {
             const result = await this.executeDepositTx();
@@ -186,7 +191,7 @@ export class DepositUserFlow extends DepositBaseFlow {
         }
       }
 
-      await nextExecutionWait;
+      await waitNextExecution();
     }
   }
 }
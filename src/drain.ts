@@ -0,0 +1,89 @@
+import { formatEther } from "ethers";
+import winston from "winston";
+
+import { L2_EXECUTION_TIMEOUT } from "./configs";
+import { SEC, unwrap } from "./utils";
+
+import type { LeaderElection } from "./leaderElection";
+import type { Mutex } from "./lock";
+import type { Signer } from "ethers";
+
+/// the process exits once exceeded, regardless of in-flight operations, to stay within the kill grace period
+const SHUTDOWN_TIMEOUT_SECONDS = +(process.env.SHUTDOWN_TIMEOUT_SECONDS ?? 25);
+
+/** Returns the `--drain-on-exit <address>` (or `--drain-on-exit=<address>`) command line argument, if any. */
+export function getDrainOnExitArg(argv: string[]): string | undefined {
+  for (let i = 0; i < argv.length; i++) {
+    if (argv[i] === "--drain-on-exit") {
+      if (argv[i + 1] == null) throw new Error("--drain-on-exit requires an address");
+      return argv[i + 1];
+    }
+    if (argv[i].startsWith("--drain-on-exit=")) return argv[i].slice("--drain-on-exit=".length);
+  }
+  return undefined;
+}
+
+/**
+ * Sends the whole balance of the wallet to `to`, minus the max fee of the drain transaction itself (its gas
+ * limit and max fee are pinned, so the fee can't exceed the amount left for it). No-op if the balance is
+ * dust, i.e. the drained amount wouldn't even cover the fee.
+ */
+export async function drainWallet(signer: Signer, to: string) {
+  const provider = unwrap(signer.provider);
+  const from = await signer.getAddress();
+  const [balance, feeData, gasLimit] = await Promise.all([
+    provider.getBalance(from),
+    provider.getFeeData(),
+    provider.estimateGas({ from, to, value: 1 }),
+  ]);
+  const maxFeePerGas = unwrap(feeData.maxFeePerGas ?? feeData.gasPrice);
+  const fee = gasLimit * maxFeePerGas;
+  const value = balance - fee;
+  if (value <= fee) {
+    winston.info(`Wallet ${from} balance ${formatEther(balance)} is dust, not draining`);
+    return;
+  }
+  const fees =
+    feeData.maxFeePerGas != null
+      ? { maxFeePerGas, maxPriorityFeePerGas: feeData.maxPriorityFeePerGas ?? maxFeePerGas }
+      : { gasPrice: maxFeePerGas };
+  const tx = await signer.sendTransaction({ to, value, gasLimit, ...fees });
+  winston.info(`Draining ${formatEther(value)} from ${from} to ${to} in tx ${tx.hash}`);
+  await provider.waitForTransaction(tx.hash, 1, L2_EXECUTION_TIMEOUT);
+  winston.info(`Drained wallet ${from}`);
+}
+
+/**
+ * Drains the wallet to `to` on SIGTERM / SIGINT, then exits. Only the leader drains, as it's the only
+ * replica sending from the wallet. The wallet lock waits for an in-flight transaction of the flows.
+ * If it takes longer than `SHUTDOWN_TIMEOUT_SECONDS` (e.g. a receipt that never comes), the process exits anyway.
+ */
+export function drainOnExit(signer: Signer, to: string, walletLock: Mutex, leaderElection: LeaderElection) {
+  let draining = false;
+  const onSignal = async (signal: NodeJS.Signals) => {
+    if (draining) return;
+    draining = true;
+    let inFlight = "waiting for the in-flight transaction of the flows";
+    setTimeout(() => {
+      winston.error(`Shutdown timed out after ${SHUTDOWN_TIMEOUT_SECONDS} seconds, abandoned: ${inFlight}`);
+      process.exit(1);
+    }, SHUTDOWN_TIMEOUT_SECONDS * SEC).unref();
+    if (leaderElection.isLeader()) {
+      winston.info(`Received ${signal}, draining the wallet to ${to}`);
+      try {
+        await walletLock.withLock(() => {
+          inFlight = `draining the wallet to ${to}`;
+          return drainWallet(signer, to);
+        });
+        // eslint-disable-next-line @typescript-eslint/no-explicit-any
+      } catch (error: any) {
+        winston.error("Drain error: " + error?.message, error?.stack);
+        process.exit(1);
+      }
+    }
+    // keeps the non-zero exit code of a shutdown on a fatal flow error
+    process.exit();
+  };
+  process.on("SIGTERM", onSignal);
+  process.on("SIGINT", onSignal);
+}
@@ -0,0 +1,61 @@
+import { isTransportError } from "./rpcLoggingProvider";
+
+export const WatchdogErrorKind = {
+  transport: "transport",
+  estimation: "estimation",
+  send: "send",
+  receipt: "receipt",
+  revert: "revert",
+  config: "config",
+} as const;
+export type WatchdogErrorKind = (typeof WatchdogErrorKind)[keyof typeof WatchdogErrorKind];
+
+/**
+ * Error of a flow with the kind of failure, so that failures can be classified in metrics and retry decisions
+ * without matching on messages. The original error, if any, is kept as `cause`.
+ */
+export class WatchdogError extends Error {
+  constructor(
+    readonly kind: WatchdogErrorKind,
+    message: string,
+    readonly cause?: unknown
+  ) {
+    super(message);
+    this.name = "WatchdogError";
+  }
+}
+
+/**
+ * Error that stops the watchdog instead of being retried (e.g. a revert with `EXIT_ON_REVERT`): the flow loop isn't
+ * restarted and `main` shuts down with a non-zero exit code.
+ */
+export class FatalError extends Error {
+  constructor(
+    message: string,
+    readonly cause?: unknown
+  ) {
+    super(message);
+    this.name = "FatalError";
+  }
+}
+
+/** Whether the error comes from the paymaster (e.g. validation revert or insufficient paymaster balance). */
+// eslint-disable-next-line @typescript-eslint/no-explicit-any
+export const isPaymasterError = (error: any): boolean =>
+  /paymaster/i.test(`${error?.message ?? ""} ${error?.info?.error?.message ?? ""}`);
+
+/**
+ * Classifies an error thrown by a stage of the given kind. Already classified errors are kept as is, and an
+ * unreachable node is a `transport` failure whatever the stage.
+ */
+// eslint-disable-next-line @typescript-eslint/no-explicit-any
+export function toWatchdogError(error: any, kind: WatchdogErrorKind): WatchdogError {
+  if (error instanceof WatchdogError) {
+    return error;
+  }
+  return new WatchdogError(
+    isTransportError(error) ? WatchdogErrorKind.transport : kind,
+    error?.message ?? String(error),
+    error
+  );
+}
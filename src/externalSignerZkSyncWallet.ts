@@ -1,29 +1,29 @@
 import { Wallet as ZkSyncWallet, EIP712Signer } from "zksync-ethers";
 import { EIP712_TX_TYPE, serializeEip712 } from "zksync-ethers/build/utils";
 
-import { GcpKmsSigner } from "./gcpKmsSigner";
-
 import type { ethers, TypedDataDomain, TypedDataField } from "ethers";
 import type { Provider } from "zksync-ethers";
 import type { TransactionRequest, TransactionResponse } from "zksync-ethers/build/types";
 
 /**
- * A ZkSync Wallet backed by GCP Cloud KMS.
+ * A ZkSync Wallet whose key lives outside the process (GCP Cloud KMS, a remote
+ * JSON-RPC signer, …).
  *
  * Extends the real ZkSyncWallet so every adapter method (deposits, withdrawals,
  * approvals, bridge helpers, …) keeps working.  A throwaway private key is fed
- * to the super-constructor — all actual signing is delegated to `GcpKmsSigner`.
+ * to the super-constructor — all actual signing is delegated to the ethers
+ * signer passed to `create()`.
  */
-export class GcpKmsZkSyncWallet extends ZkSyncWallet {
-  private readonly kmsSigner: GcpKmsSigner;
-  private kmsAddress: string | null = null;
+export class ExternalSignerZkSyncWallet extends ZkSyncWallet {
+  private readonly externalSigner: ethers.AbstractSigner;
+  private externalAddress: string | null = null;
 
   /**
-   * Use `GcpKmsZkSyncWallet.create()` instead of calling this directly.
+   * Use `ExternalSignerZkSyncWallet.create()` instead of calling this directly.
    */
   private constructor(
-    kmsSigner: GcpKmsSigner,
-    kmsAddress: string,
+    externalSigner: ethers.AbstractSigner,
+    externalAddress: string,
     providerL2?: Provider,
     providerL1?: ethers.Provider
   ) {
@@ -32,13 +32,13 @@ export class GcpKmsZkSyncWallet extends ZkSyncWallet {
     const dummyKey = "0x0000000000000000000000000000000000000000000000000000000000000001";
     super(dummyKey, providerL2, providerL1);
 
-    this.kmsSigner = kmsSigner;
-    this.kmsAddress = kmsAddress;
+    this.externalSigner = externalSigner;
+    this.externalAddress = externalAddress;
 
     // Override the `address` property that ethers.BaseWallet sets as a
     // non-writable own property.  It is configurable, so defineProperty works.
     Object.defineProperty(this, "address", {
-      value: kmsAddress,
+      value: externalAddress,
       writable: false,
       enumerable: true,
       configurable: true,
@@ -56,16 +56,16 @@ export class GcpKmsZkSyncWallet extends ZkSyncWallet {
   }
 
   /**
-   * Async factory — resolves the KMS address before construction.
+   * Async factory — resolves the signer address before construction.
    */
   static async create(
-    kmsResourceName: string,
+    externalSigner: ethers.AbstractSigner,
     providerL2?: Provider,
     providerL1?: ethers.Provider
-  ): Promise<GcpKmsZkSyncWallet> {
-    const kmsSigner = new GcpKmsSigner(kmsResourceName, providerL2 ?? null);
-    const kmsAddress = await kmsSigner.getAddress();
-    return new GcpKmsZkSyncWallet(kmsSigner, kmsAddress, providerL2, providerL1);
+  ): Promise<ExternalSignerZkSyncWallet> {
+    const signer = externalSigner.connect(providerL2 ?? null) as ethers.AbstractSigner;
+    const address = await signer.getAddress();
+    return new ExternalSignerZkSyncWallet(signer, address, providerL2, providerL1);
   }
 
   // ---- signing overrides ----------------------------------------------------
@@ -73,8 +73,8 @@ export class GcpKmsZkSyncWallet extends ZkSyncWallet {
   override async signTransaction(tx: TransactionRequest): Promise<string> {
     const populated = await this.populateTransaction(tx);
     if (populated.type !== EIP712_TX_TYPE) {
-      // Standard (non-EIP712) transaction — delegate to KMS signer directly.
-      return await this.kmsSigner.signTransaction(populated as ethers.TransactionRequest);
+      // Standard (non-EIP712) transaction — delegate to the external signer directly.
+      return await this.externalSigner.signTransaction(populated as ethers.TransactionRequest);
     }
     // EIP-712 ZkSync transaction
     populated.customData!.customSignature = await this.eip712.sign(populated);
@@ -86,7 +86,7 @@ export class GcpKmsZkSyncWallet extends ZkSyncWallet {
   }
 
   override async signMessage(message: string | Uint8Array): Promise<string> {
-    return await this.kmsSigner.signMessage(message);
+    return await this.externalSigner.signMessage(message);
   }
 
   override async signTypedData(
@@ -94,33 +94,33 @@ export class GcpKmsZkSyncWallet extends ZkSyncWallet {
     types: Record<string, TypedDataField[]>,
     value: Record<string, unknown>
   ): Promise<string> {
-    return await this.kmsSigner.signTypedData(domain, types, value);
+    return await this.externalSigner.signTypedData(domain, types, value);
   }
 
   // ---- L1 signer override ---------------------------------------------------
 
-  /** Returns an ethers-level KMS signer connected to the L1 provider. */
+  /** Returns the ethers-level external signer connected to the L1 provider. */
   override _signerL1(): ethers.Wallet {
     // The adapter typings say `ethers.Wallet`, but the only methods actually
     // called on _signerL1() are Signer methods (getNonce, sendTransaction,
-    // getAddress, etc.).  We return our KMS signer cast through `as any`
+    // getAddress, etc.).  We return the external signer cast through `as any`
     // because it satisfies the runtime contract even though the static type
     // is narrower.
-    return this.kmsSigner.connect(this._providerL1()) as unknown as ethers.Wallet;
+    return this.externalSigner.connect(this._providerL1()) as unknown as ethers.Wallet;
   }
 
-  override _signerL2(): GcpKmsZkSyncWallet {
+  override _signerL2(): ExternalSignerZkSyncWallet {
     return this;
   }
 
   /** Override to avoid constructing a new ethers.Wallet with the dummy signingKey. */
   override ethWallet(): ethers.Wallet {
-    return this.kmsSigner.connect(this._providerL1()) as unknown as ethers.Wallet;
+    return this.externalSigner.connect(this._providerL1()) as unknown as ethers.Wallet;
   }
 
   // ---- getAddress override --------------------------------------------------
 
   override async getAddress(): Promise<string> {
-    return this.kmsAddress!;
+    return this.externalAddress!;
   }
 }
@@ -0,0 +1,75 @@
+import "dotenv/config";
+import { Gauge } from "prom-client";
+
+import { BaseFlow } from "./baseFlow";
+import { SEC } from "./utils";
+
+import type { JsonRpcApiProvider } from "ethers";
+
+const FLOW_NAME = "fee_history";
+const FEE_HISTORY_BLOCKS = +(process.env.FLOW_FEE_HISTORY_BLOCKS ?? 10);
+
+/**
+ * Queries `eth_feeHistory` over the most recent blocks. Wallets and dapps estimate fees with it, so a broken
+ * fee history endpoint breaks their fee estimation even while transactions still go through.
+ */
+export class FeeHistoryProbeFlow extends BaseFlow {
+  private metricBaseFee: Gauge;
+  private metricBaseFeeTrend: Gauge;
+
+  constructor(
+    private provider: JsonRpcApiProvider,
+    private intervalMs: number
+  ) {
+    super(FLOW_NAME);
+    this.metricBaseFee = new Gauge({
+      name: "watchdog_fee_history_base_fee",
+      help: "Base fee per gas of the next block in wei, as returned by eth_feeHistory",
+    });
+    this.metricBaseFeeTrend = new Gauge({
+      name: "watchdog_fee_history_base_fee_trend",
+      help: "Relative change of the base fee over the eth_feeHistory window (0.1 is a 10% increase)",
+    });
+  }
+
+  public async run() {
+    while (true) {
+      const waitNextExecution = this.startCycle(this.intervalMs);
+
+      try {
+        this.metricRecorder.recordFlowStart();
+
+        const baseFees = await this.metricRecorder.stepExecution({
+          stepName: "fee_history",
+          stepTimeoutMs: 10 * SEC,
+          fn: async () => {
+            const feeHistory = (await this.provider.send("eth_feeHistory", [
+              `0x${FEE_HISTORY_BLOCKS.toString(16)}`,
+              "latest",
+              [],
+            ])) as { baseFeePerGas?: string[] };
+            // one more entry than blocks, the last one is the base fee of the next block
+            const baseFees = (feeHistory?.baseFeePerGas ?? []).map((baseFee) => BigInt(baseFee));
+            if (baseFees.length === 0) {
+              throw new Error(`eth_feeHistory returned no base fees: ${JSON.stringify(feeHistory)}`);
+            }
+            return baseFees;
+          },
+        });
+        const first = baseFees[0];
+        const last = baseFees[baseFees.length - 1];
+        this.logger.debug(`Base fee went from ${first} to ${last} wei over ${baseFees.length - 1} blocks`);
+        this.metricBaseFee.set(Number(last));
+        this.metricBaseFeeTrend.set(first > 0n ? Number(last - first) / Number(first) : 0);
+
+        this.metricRecorder.recordFlowSuccess();
+        // eslint-disable-next-line @typescript-eslint/no-explicit-any
+      } catch (error: any) {
+        this.logger.error("eth_feeHistory probe error: " + error?.message, error?.stack);
+        this.metricRecorder.recordFlowFailure();
+      }
+
+      await waitNextExecution();
+    }
+  }
+}
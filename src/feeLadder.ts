@@ -0,0 +1,149 @@
+import "dotenv/config";
+import { Histogram } from "prom-client";
+
+import { BaseFlow } from "./baseFlow";
+import { L2_EXECUTION_TIMEOUT } from "./configs";
+import { getFeeOverrides } from "./fees";
+import { checkChainIdUnchanged } from "./rpcLoggingProvider";
+import { isSpendBudgetExhausted } from "./spendBudget";
+import { SEC, unwrap } from "./utils";
+
+import type { Mutex } from "./lock";
+import type { Provider as EthersProvider, Signer as EthersSigner, TransactionResponse } from "ethers";
+
+const FLOW_NAME = "fee_ladder";
+/// multipliers of the estimated fees, one transaction is sent at each of them per cycle
+const FEE_LADDER_LEVELS = (process.env.FLOW_FEE_LADDER_LEVELS ?? "1,1.5,2")
+  .split(",")
+  .map((level) => +level.trim())
+  .filter((level) => level > 0);
+
+/** Returns the fee multiplied by the level, with 2 decimals of precision. */
+const scaleFee = (fee: bigint | null | undefined, level: number): bigint | null | undefined =>
+  fee == null ? fee : (fee * BigInt(Math.round(level * 100))) / 100n;
+
+/**
+ * Experimental: sends a 1 wei self-transfer at each of the stepped fee levels `FLOW_FEE_LADDER_LEVELS` with
+ * consecutive nonces, and records the inclusion latency of each level, for a fee/inclusion time curve to tune the
+ * fee strategy of the transfer flow with. Levels are sent in order, so a level is never included before the
+ * previous ones. Spends several transactions per cycle, so it's strictly opt-in.
+ */
+export class FeeLadderFlow extends BaseFlow {
+  private metricInclusionLatency: Histogram;
+
+  constructor(
+    private wallet: EthersSigner,
+    private receiptProvider: EthersProvider,
+    private l2WalletLock: Mutex,
+    private intervalMs: number
+  ) {
+    super(FLOW_NAME);
+    this.metricInclusionLatency = new Histogram({
+      name: "watchdog_fee_ladder_inclusion_latency",
+      help: "Seconds from sending a transfer of the fee ladder until its inclusion, by `level` (fee multiplier)",
+      labelNames: ["level"],
+      buckets: [0.25, 0.5, 1, 2, 5, 10, 30, 60],
+    });
+  }
+
+  private async step() {
+    const address = await this.wallet.getAddress();
+    const populated = await this.metricRecorder.estimationStepExecution({
+      stepName: "estimation",
+      stepTimeoutMs: 10 * SEC,
+      fn: async ({ recordStepGas, recordStepGasPrice }) => {
+        const nonce = await this.wallet.getNonce("latest");
+        const populated = await this.wallet.populateTransaction({
+          to: address,
+          value: 1,
+          ...getFeeOverrides(),
+          nonce,
+        });
+        recordStepGas(unwrap(populated.gasLimit));
+        recordStepGasPrice(unwrap(populated.maxFeePerGas ?? populated.gasPrice));
+        return populated;
+      },
+    });
+
+    const sent: { level: number; response: TransactionResponse; sentAt: number }[] = [];
+    try {
+      await this.metricRecorder.stepExecution({
+        stepName: "send",
+        stepTimeoutMs: 10 * SEC * FEE_LADDER_LEVELS.length,
+        fn: async () => {
+          // not waiting for the inclusion in between, so that all the levels compete for the same blocks
+          for (const [index, level] of FEE_LADDER_LEVELS.entries()) {
+            const response = await this.wallet.sendTransaction({
+              ...populated,
+              nonce: unwrap(populated.nonce) + index,
+              gasPrice: scaleFee(populated.gasPrice, level),
+              maxFeePerGas: scaleFee(populated.maxFeePerGas, level),
+              maxPriorityFeePerGas: scaleFee(populated.maxPriorityFeePerGas, level),
+            });
+            sent.push({ level, response, sentAt: Date.now() });
+          }
+        },
+      });
+    } catch (error) {
+      // the levels already sent would otherwise still be pending once the wallet lock is released, and the next
+      // sender (e.g. the transfer flow) would collide with their nonces
+      await this.waitForPartialLadder(sent.map(({ response }) => response.hash));
+      throw error;
+    }
+
+    await this.metricRecorder.stepExecution({
+      stepName: "execution",
+      stepTimeoutMs: L2_EXECUTION_TIMEOUT,
+      fn: () =>
+        Promise.all(
+          sent.map(async ({ level, response, sentAt }) => {
+            const receipt = unwrap(await this.receiptProvider.waitForTransaction(response.hash, 1));
+            const latency = (Date.now() - sentAt) / 1000;
+            this.metricInclusionLatency.observe({ level: level.toString() }, latency);
+            this.metricRecorder.recordTxCost(receipt.gasUsed * receipt.gasPrice);
+            this.logger.info(`Tx ${response.hash} at ${level}x fees included in ${latency} seconds`);
+          })
+        ),
+    });
+  }
+
+  /** Waits for the transactions sent before a failure of the send step to be included, or for the timeout. */
+  private async waitForPartialLadder(hashes: string[]) {
+    if (hashes.length === 0) return;
+    this.logger.warn(`Fee ladder send failed after ${hashes.length} level(s), waiting for them to be included`);
+    const results = await Promise.allSettled(
+      hashes.map((hash) => this.receiptProvider.waitForTransaction(hash, 1, L2_EXECUTION_TIMEOUT))
+    );
+    const pending = hashes.filter((_, i) => {
+      const result = results[i];
+      return result.status === "rejected" || result.value == null;
+    });
+    if (pending.length > 0) {
+      this.logger.error(`Fee ladder txs ${pending.join(", ")} still not included, the next sends may replace them`);
+    }
+  }
+
+  public async run() {
+    while (true) {
+      const waitNextExecution = this.startCycle(this.intervalMs);
+
+      try {
+        this.metricRecorder.recordFlowStart();
+        if (isSpendBudgetExhausted()) {
+          this.logger.warn("Spend budget exhausted, skipping the fee ladder");
+          this.metricRecorder.recordFlowSkipped();
+        } else {
+          await checkChainIdUnchanged(this.wallet.provider);
+          await this.l2WalletLock.withLock(() => this.step());
+          this.metricRecorder.recordFlowSuccess();
+        }
+        // eslint-disable-next-line @typescript-eslint/no-explicit-any
+      } catch (error: any) {
+        this.logger.error("Fee ladder error: " + error?.message, error?.stack);
+        this.metricRecorder.recordFlowFailure();
+      }
+
+      await waitNextExecution();
+    }
+  }
+}
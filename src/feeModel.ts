@@ -0,0 +1,32 @@
+import { Gauge } from "prom-client";
+import winston from "winston";
+
+import type { Provider } from "zksync-ethers";
+
+const metricFeeModelVersion = new Gauge({
+  name: "watchdog_fee_model_version",
+  help: "Version of the L2 fee model reported by zks_getFeeParams (1 - fixed gas per pubdata, 2 - dynamic)",
+});
+
+/**
+ * Detects the fee model of the chain from `zks_getFeeParams` (`{ V1: ... }` or `{ V2: ... }`). With V2 (protocol
+ * 1.4.1+) the gas per pubdata depends on the L1 pubdata price, so the hardcoded default limit can be wrong.
+ * Returns `null` if the node doesn't report it (e.g. ZKsync OS), the metric is then not emitted.
+ */
+export async function detectFeeModelVersion(provider: Provider): Promise<number | null> {
+  try {
+    const feeParams = await provider.send("zks_getFeeParams", []);
+    const version = feeParams?.V2 != null ? 2 : feeParams?.V1 != null ? 1 : null;
+    if (version == null) {
+      winston.warn(`Unknown fee model in zks_getFeeParams: ${JSON.stringify(feeParams)}`);
+      return null;
+    }
+    winston.info(`Detected fee model V${version}`);
+    metricFeeModelVersion.set(version);
+    return version;
+    // eslint-disable-next-line @typescript-eslint/no-explicit-any
+  } catch (error: any) {
+    winston.warn(`Failed to detect the fee model: ${error?.message}`);
+    return null;
+  }
+}
@@ -0,0 +1,83 @@
+import { MIN_PRIORITY_FEE_WEI, OVERRIDE_MAX_FEE_WEI, OVERRIDE_PRIORITY_FEE_WEI } from "./configs";
+
+import type { BigNumberish } from "ethers";
+
+type FeeFields = {
+  gasPrice?: BigNumberish | null;
+  maxFeePerGas?: BigNumberish | null;
+  maxPriorityFeePerGas?: BigNumberish | null;
+};
+
+/** Fees configured via `OVERRIDE_MAX_FEE_WEI` / `OVERRIDE_PRIORITY_FEE_WEI`, to be spread into a transaction request. */
+export const getFeeOverrides = (): { maxFeePerGas?: bigint; maxPriorityFeePerGas?: bigint } => {
+  const overrides: { maxFeePerGas?: bigint; maxPriorityFeePerGas?: bigint } = {};
+  if (OVERRIDE_MAX_FEE_WEI != null) overrides.maxFeePerGas = OVERRIDE_MAX_FEE_WEI;
+  if (OVERRIDE_PRIORITY_FEE_WEI != null) overrides.maxPriorityFeePerGas = OVERRIDE_PRIORITY_FEE_WEI;
+  return overrides;
+};
+
+/**
+ * Raises an estimated priority fee below `MIN_PRIORITY_FEE_WEI` to the floor. The max fee is raised by the same
+ * amount to keep the base fee headroom. Returns `null` if the estimate already meets the floor.
+ */
+export const applyPriorityFeeFloor = <T extends FeeFields>(tx: T): T | null => {
+  if (tx.maxPriorityFeePerGas == null || BigInt(tx.maxPriorityFeePerGas) >= MIN_PRIORITY_FEE_WEI) {
+    return null;
+  }
+  const raise = MIN_PRIORITY_FEE_WEI - BigInt(tx.maxPriorityFeePerGas);
+  return {
+    ...tx,
+    maxPriorityFeePerGas: MIN_PRIORITY_FEE_WEI,
+    maxFeePerGas: tx.maxFeePerGas != null ? BigInt(tx.maxFeePerGas) + raise : tx.maxFeePerGas,
+  };
+};
+
+const FEE_FIELDS = ["gasPrice", "maxFeePerGas", "maxPriorityFeePerGas"] as const;
+
+const bumpFee = (fee: bigint, percent: bigint, times: number): bigint => {
+  let bumped = fee;
+  for (let i = 0; i < times; i++) {
+    bumped = (bumped * (100n + percent)) / 100n;
+  }
+  return bumped;
+};
+
+/** Returns a copy of `tx` with all of its set fee fields increased `times` times by `percent` (compounding). */
+export const bumpFees = <T extends FeeFields>(tx: T, percent: number, times: number): T => {
+  const bumped: FeeFields = { ...tx };
+  for (const field of FEE_FIELDS) {
+    const fee = tx[field];
+    if (fee != null) {
+      bumped[field] = bumpFee(BigInt(fee), BigInt(percent), times);
+    }
+  }
+  return bumped as T;
+};
+
+/**
+ * Whether the node rejected a transaction because another one with the same nonce is pending
+ * and our fees are not high enough to replace it.
+ */
+// eslint-disable-next-line @typescript-eslint/no-explicit-any
+export const isReplacementUnderpriced = (error: any): boolean => {
+  // ethers maps the node error to this code, but errors from authorized (Prividium) requests are raw
+  return (
+    error?.code === "REPLACEMENT_UNDERPRICED" ||
+    /replacement transaction underpriced|replacement fee too low/i.test(error?.message ?? "")
+  );
+};
+
+/** Whether the node rejected a transaction because it already has it. */
+// eslint-disable-next-line @typescript-eslint/no-explicit-any
+export const isDuplicateTransaction = (error: any): boolean => {
+  return /already known|known transaction|already imported/i.test(error?.message ?? "");
+};
+
+/**
+ * Whether the node rejected a transaction because its nonce was already used, either by the transaction itself
+ * (mined) or by another one of the same account.
+ */
+// eslint-disable-next-line @typescript-eslint/no-explicit-any
+export const isNonceTooLow = (error: any): boolean => {
+  return error?.code === "NONCE_EXPIRED" || /nonce too low/i.test(error?.message ?? "");
+};
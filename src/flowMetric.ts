@@ -1,10 +1,34 @@
 import { Counter, Gauge, Histogram } from "prom-client";
 
+import { sendHeartbeat } from "./heartbeat";
+import { recordSpend } from "./spendBudget";
+import { appendCycleTrace } from "./traceCsv";
 import { withTimeout } from "./utils";
 
+import type { CycleTrace } from "./traceCsv";
 import type { TimeoutCtx } from "./utils";
 import type { Logger } from "winston";
 
+/// failures only flip `watchdog_status` to unhealthy once the flow hasn't succeeded for this long (ms)
+const STATUS_GRACE_PERIOD = +(process.env.STATUS_GRACE_PERIOD ?? 0);
+
+/** Parses per-flow metric names, e.g. `transfer=selftransfer,deposit=l1_deposit`. */
+const parseFlowMetricNames = (flowMetricNames: string | undefined): Record<string, string> => {
+  const names: Record<string, string> = {};
+  for (const entry of (flowMetricNames ?? "").split(",")) {
+    const [flowName, metricName] = entry.split("=").map((part) => part.trim());
+    if (!flowName || !metricName) continue;
+    names[flowName] = metricName;
+  }
+  return names;
+};
+
+/// `flow` label of the metrics of each flow, overriding its name (e.g. to keep existing dashboards working)
+const METRICS_FLOW_NAMES = parseFlowMetricNames(process.env.METRICS_FLOW_NAMES);
+/// base name of the shared metrics of each flow, overriding `watchdog` (e.g. `watchdog_selftransfer`)
+const METRICS_FLOW_NAMESPACES = parseFlowMetricNames(process.env.METRICS_FLOW_NAMESPACES);
+const DEFAULT_METRICS_NAMESPACE = "watchdog";
+
 export const StatusNoSkip = {
   OK: "OK",
   FAIL: "FAIL",
@@ -17,7 +41,24 @@ export const Status = {
 } as const;
 export type Status = (typeof Status)[keyof typeof Status];
 
-/// singleton for metric storage
+const metricWalletInfo = new Gauge({
+  name: "watchdog_wallet_info",
+  help: "Watchdog wallet information",
+  labelNames: ["l1_l2_wallet_address", "l2_base_token_address"],
+});
+
+const metricStartupDuration = new Gauge({
+  name: "watchdog_startup_duration_seconds",
+  help: "Seconds from process start until the flows were started (env parsing, providers, startup checks)",
+});
+
+const metricWalletBalance = new Gauge({
+  name: "watchdog_wallet_balance",
+  help: "Watchdog wallet balance",
+  labelNames: ["balance_type"],
+});
+
+/// storage of the shared metrics, one per metric base name
 class FlowMetricStore {
   public metric_latency: Gauge;
   public metric_step_timestamp: Gauge; //in ms
@@ -28,80 +69,141 @@ class FlowMetricStore {
   public metric_step_gas: Gauge;
   public metric_step_gas_price: Gauge;
   public metric_step_gas_cost: Gauge;
-  public metric_wallet_info: Gauge;
-  public metric_wallet_balance: Gauge;
+  public metric_tx_underpriced_bump: Counter;
+  public metric_tx_estimate_status: Gauge;
+  public metric_tx_cost_wei: Counter;
+  public metric_seconds_since_success: Gauge;
+  public metric_tick_skipped: Counter;
+  public metric_priority_fee_floor_applied: Counter;
+  public metric_loop_restarts: Counter;
+  public metric_loop_alive: Gauge;
+  /// last success (or flow creation, until the first success) per flow, in ms
+  public last_success_ms = new Map<string, number>();
 
-  constructor() {
+  constructor(namespace: string) {
     this.metric_latency = new Gauge({
-      name: "watchdog_latency",
+      name: `${namespace}_latency`,
       help: "Watchdog step latencies for all flows",
       labelNames: ["flow", "stage"],
     });
     this.metric_latency_total = new Gauge({
-      name: "watchdog_latency_total",
+      name: `${namespace}_latency_total`,
       help: "Watchdog latency totals for all flows",
       labelNames: ["flow"],
     });
-    this.metric_status = new Gauge({ name: "watchdog_status", help: "Watchdog flow status", labelNames: ["flow"] });
+    this.metric_status = new Gauge({ name: `${namespace}_status`, help: "Watchdog flow status", labelNames: ["flow"] });
     // DEPRECATED: use `metric_status_counter` instead
     this.metric_status_hist = new Histogram({
-      name: "watchdog_status_hist",
+      name: `${namespace}_status_hist`,
       help: "Watchdog flow status histogram",
       labelNames: ["flow"],
     });
     this.metric_status_counter = new Counter({
-      name: "watchdog_status_counter",
+      name: `${namespace}_status_counter`,
       help: "Watchdog flow status counter",
       // outcome can be "success", "failure" and "skipped"
       labelNames: ["flow", "outcome"],
     });
     this.metric_step_timestamp = new Gauge({
-      name: "watchdog_step_timestamp",
+      name: `${namespace}_step_timestamp`,
       help: "Watchdog last step completion timestamp in ms for all flows",
       labelNames: ["flow", "step"],
     });
     this.metric_step_gas = new Gauge({
-      name: "watchdog_step_gas",
+      name: `${namespace}_step_gas`,
       help: "Watchdog step gas",
       labelNames: ["flow", "step"],
     });
     this.metric_step_gas_price = new Gauge({
-      name: "watchdog_step_gas_price",
+      name: `${namespace}_step_gas_price`,
       help: "Watchdog step gas price (either limit or actualy used)",
       labelNames: ["flow", "step"],
     });
     this.metric_step_gas_cost = new Gauge({
-      name: "watchdog_step_gas_cost",
+      name: `${namespace}_step_gas_cost`,
       help: "Watchdog step gas cost (price * used)",
       labelNames: ["flow", "step"],
     });
-    this.metric_wallet_info = new Gauge({
-      name: "watchdog_wallet_info",
-      help: "Watchdog wallet information",
-      labelNames: ["l1_l2_wallet_address", "l2_base_token_address"],
+    this.metric_loop_restarts = new Counter({
+      name: `${namespace}_loop_restarts`,
+      help: "Number of restarts of the flow loop after it crashed",
+      labelNames: ["flow"],
+    });
+    this.metric_loop_alive = new Gauge({
+      name: `${namespace}_loop_alive`,
+      help: "1 while the flow loop is running, 0 while it's restarting after a crash or was given up",
+      labelNames: ["flow"],
+    });
+    this.metric_tx_underpriced_bump = new Counter({
+      name: `${namespace}_tx_underpriced_bump`,
+      help: "Number of fee bumps after the node rejected a transaction as replacement underpriced",
+      labelNames: ["flow"],
+    });
+    this.metric_tx_estimate_status = new Gauge({
+      name: `${namespace}_tx_estimate_status`,
+      help: "Status of the last transaction estimation (1 - success, 0 - failure), independent of the send status",
+      labelNames: ["flow"],
+    });
+    this.metric_tx_cost_wei = new Counter({
+      name: `${namespace}_tx_cost_wei`,
+      help: "Cumulative fees actually paid by mined watchdog transactions (gas used * effective gas price) in wei",
+      labelNames: ["flow"],
     });
-    this.metric_wallet_balance = new Gauge({
-      name: "watchdog_wallet_balance",
-      help: "Watchdog wallet balance",
-      labelNames: ["balance_type"],
+    this.metric_tick_skipped = new Counter({
+      name: `${namespace}_tick_skipped`,
+      help: "Number of fixed rate schedule ticks skipped because the previous cycle of the flow overran",
+      labelNames: ["flow"],
+    });
+    this.metric_priority_fee_floor_applied = new Counter({
+      name: `${namespace}_priority_fee_floor_applied`,
+      help: "Number of transactions whose estimated priority fee was raised to MIN_PRIORITY_FEE_WEI",
+      labelNames: ["flow"],
+    });
+    const lastSuccess = this.last_success_ms;
+    this.metric_seconds_since_success = new Gauge({
+      name: `${namespace}_seconds_since_success`,
+      help: "Seconds since the last successful execution of the flow (since startup if it never succeeded)",
+      labelNames: ["flow"],
+      // evaluated on every scrape, so the value keeps growing even while a flow is stuck
+      collect() {
+        const now = Date.now();
+        for (const [flow, timestamp] of lastSuccess) {
+          this.set({ flow }, (now - timestamp) / 1000);
+        }
+      },
     });
   }
 }
-const store = new FlowMetricStore();
+const stores = new Map<string, FlowMetricStore>();
+
+const getStore = (namespace: string): FlowMetricStore => {
+  let store = stores.get(namespace);
+  if (store == null) {
+    store = new FlowMetricStore(namespace);
+    stores.set(namespace, store);
+  }
+  return store;
+};
+// registered at startup like the other metrics, the remapped ones once their flow is created
+getStore(DEFAULT_METRICS_NAMESPACE);
 
 export function recordWalletInfo(walletAddress: string) {
   // todo: also report `l2_base_token_address`. Reporting an empty string for now.
   // calling
-  store.metric_wallet_info.set({ l1_l2_wallet_address: walletAddress, l2_base_token_address: "" }, 1);
+  metricWalletInfo.set({ l1_l2_wallet_address: walletAddress, l2_base_token_address: "" }, 1);
+}
+
+export function recordStartupDuration(seconds: number) {
+  metricStartupDuration.set(seconds);
 }
 
 export function recordL2BaseTokenBalance(balance: number | bigint) {
-  store.metric_wallet_balance.set({ balance_type: "l2_base_token" }, Number(balance));
+  metricWalletBalance.set({ balance_type: "l2_base_token" }, Number(balance));
 }
 
 export function recordL1Balances(baseTokenBalance: number | bigint, ethBalance: number | bigint) {
-  store.metric_wallet_balance.set({ balance_type: "l1_base_token" }, Number(baseTokenBalance));
-  store.metric_wallet_balance.set({ balance_type: "l1_eth" }, Number(ethBalance));
+  metricWalletBalance.set({ balance_type: "l1_base_token" }, Number(baseTokenBalance));
+  metricWalletBalance.set({ balance_type: "l1_eth" }, Number(ethBalance));
 }
 
 type Numberish = number | bigint | string;
@@ -120,39 +222,51 @@ export interface StepHelpers {
   timeoutMs: number;
 }
 
+export type StepExecutionParams<T> = {
+  stepName: string;
+  stepTimeoutMs: number;
+  fn: (helpers: StepHelpers) => Promise<T>;
+};
+
 export class FlowMetricRecorder {
   startTime: number | null = null;
   private _lastStepLatency: number | null = null;
   private _lastExecutionTotalLatency: number | null = null;
+  /// value of the `flow` label, the flow name unless remapped by `METRICS_FLOW_NAMES`
+  private flowLabel: string;
+  /// shared metrics under the base name of the flow, `watchdog` unless remapped by `METRICS_FLOW_NAMESPACES`
+  private store: FlowMetricStore;
+  /// metrics of the current cycle, for `TRACE_CSV_PATH`
+  private cycleTrace: CycleTrace = { latencies: {}, gas: {}, costWei: 0n };
   constructor(
     private flowName: string,
     private logger: Logger
-  ) {}
+  ) {
+    this.flowLabel = METRICS_FLOW_NAMES[flowName] ?? flowName;
+    this.store = getStore(METRICS_FLOW_NAMESPACES[flowName] ?? DEFAULT_METRICS_NAMESPACE);
+    if (!this.store.last_success_ms.has(this.flowLabel)) {
+      this.store.last_success_ms.set(this.flowLabel, Date.now());
+    }
+  }
 
   public recordFlowStart() {
     this.startTime = Date.now();
+    this.cycleTrace = { latencies: {}, gas: {}, costWei: 0n };
     this.logger.info("Flow started");
   }
 
-  public async stepExecution<T>({
-    stepName,
-    stepTimeoutMs,
-    fn,
-  }: {
-    stepName: string;
-    stepTimeoutMs: number;
-    fn: (helpers: StepHelpers) => Promise<T>;
-  }): Promise<T> {
+  public async stepExecution<T>({ stepName, stepTimeoutMs, fn }: StepExecutionParams<T>): Promise<T> {
     const start = Date.now();
     const makeHelpers = (ctx: TimeoutCtx): StepHelpers => ({
       recordStepGas: (gas: Numberish) => {
-        store.metric_step_gas.set({ flow: this.flowName, step: stepName }, Number(gas));
+        this.store.metric_step_gas.set({ flow: this.flowLabel, step: stepName }, Number(gas));
+        this.cycleTrace.gas[stepName] = Number(gas);
       },
       recordStepGasPrice: (price: Numberish) => {
-        store.metric_step_gas_price.set({ flow: this.flowName, step: stepName }, Number(price));
+        this.store.metric_step_gas_price.set({ flow: this.flowLabel, step: stepName }, Number(price));
       },
       recordStepGasCost: (cost: Numberish) => {
-        store.metric_step_gas_cost.set({ flow: this.flowName, step: stepName }, Number(cost));
+        this.store.metric_step_gas_cost.set({ flow: this.flowLabel, step: stepName }, Number(cost));
       },
       signal: ctx.signal,
       timeoutMs: ctx.timeoutMs,
@@ -160,22 +274,68 @@ export class FlowMetricRecorder {
     const ret = await withTimeout((ctx) => fn(makeHelpers(ctx)), stepTimeoutMs, `step ${stepName}`);
     const end = Date.now();
     const latency = (end - start) / 1000; // in seconds
-    store.metric_latency.set({ flow: this.flowName, stage: stepName }, latency);
+    this.store.metric_latency.set({ flow: this.flowLabel, stage: stepName }, latency);
     this._lastStepLatency = latency;
-    store.metric_step_timestamp.set({ flow: this.flowName, step: stepName }, end);
+    this.cycleTrace.latencies[stepName] = latency;
+    this.store.metric_step_timestamp.set({ flow: this.flowLabel, step: stepName }, end);
     this.logger.info(`Step ${stepName} took ${latency} seconds`);
     return ret;
   }
 
+  /**
+   * Same as `stepExecution`, but additionally records the outcome in `watchdog_tx_estimate_status`,
+   * so that a failing estimation endpoint can be told apart from a failing submission path.
+   */
+  public async estimationStepExecution<T>(params: StepExecutionParams<T>): Promise<T> {
+    try {
+      const ret = await this.stepExecution(params);
+      this.store.metric_tx_estimate_status.set({ flow: this.flowLabel }, 1);
+      return ret;
+    } catch (error) {
+      this.store.metric_tx_estimate_status.set({ flow: this.flowLabel }, 0);
+      throw error;
+    }
+  }
+
+  /// to be called once per mined transaction, the metric is cumulative
+  public recordTxCost(cost: Numberish) {
+    this.store.metric_tx_cost_wei.inc({ flow: this.flowLabel }, Number(cost));
+    recordSpend(BigInt(cost));
+    this.cycleTrace.costWei += BigInt(cost);
+  }
+
+  public recordLoopAlive(alive: boolean) {
+    this.store.metric_loop_alive.set({ flow: this.flowLabel }, alive ? 1 : 0);
+  }
+
+  public recordLoopRestart() {
+    this.store.metric_loop_restarts.inc({ flow: this.flowLabel });
+  }
+
+  public recordTickSkipped(count: number) {
+    this.store.metric_tick_skipped.inc({ flow: this.flowLabel }, count);
+  }
+
+  public recordPriorityFeeFloorApplied() {
+    this.store.metric_priority_fee_floor_applied.inc({ flow: this.flowLabel });
+  }
+
+  public recordUnderpricedBump() {
+    this.store.metric_tx_underpriced_bump.inc({ flow: this.flowLabel });
+  }
+
   public recordFlowSuccess() {
     if (this.startTime) {
       const endTime = Date.now();
       const latency = (endTime - this.startTime) / 1000; // in seconds
-      store.metric_latency_total.set({ flow: this.flowName }, latency);
-      store.metric_status.set({ flow: this.flowName }, 1);
-      store.metric_status_hist.observe({ flow: this.flowName }, 1);
-      store.metric_status_counter.inc({ flow: this.flowName, outcome: "success" });
+      this.store.metric_latency_total.set({ flow: this.flowLabel }, latency);
+      this.store.metric_status.set({ flow: this.flowLabel }, 1);
+      this.store.metric_status_hist.observe({ flow: this.flowLabel }, 1);
+      this.store.metric_status_counter.inc({ flow: this.flowLabel, outcome: "success" });
       this._lastExecutionTotalLatency = latency;
+      this.store.last_success_ms.set(this.flowLabel, endTime);
+      sendHeartbeat(this.flowName);
+      appendCycleTrace(this.flowLabel, "success", latency, this.cycleTrace);
       this.startTime = null;
       this.logger.info(`Flow completed in ${latency} seconds`);
     } else {
@@ -187,8 +347,9 @@ export class FlowMetricRecorder {
     if (this.startTime) {
       const endTime = Date.now();
       const latency = (endTime - this.startTime) / 1000; // in seconds
-      store.metric_status.set({ flow: this.flowName }, 0.5);
-      store.metric_status_counter.inc({ flow: this.flowName, outcome: "skipped" });
+      this.store.metric_status.set({ flow: this.flowLabel }, 0.5);
+      this.store.metric_status_counter.inc({ flow: this.flowLabel, outcome: "skipped" });
+      appendCycleTrace(this.flowLabel, "skipped", latency, this.cycleTrace);
       this.startTime = null;
       this.logger.info(`Flow skipped after ${latency} seconds`);
     } else {
@@ -196,10 +357,22 @@ export class FlowMetricRecorder {
     }
   }
 
+  /// debounces isolated transient failures, persistent ones still flip the status once the grace period is over
+  private recordStatusFailure() {
+    const lastSuccess = this.store.last_success_ms.get(this.flowLabel) ?? 0;
+    if (Date.now() - lastSuccess >= STATUS_GRACE_PERIOD) {
+      this.store.metric_status.set({ flow: this.flowLabel }, 0);
+    } else {
+      this.logger.info("Failure within the status grace period, status not changed yet");
+    }
+  }
+
   public recordFlowFailure() {
-    store.metric_status.set({ flow: this.flowName }, 0);
-    store.metric_status_hist.observe({ flow: this.flowName }, 0);
-    store.metric_status_counter.inc({ flow: this.flowName, outcome: "failure" });
+    this.recordStatusFailure();
+    this.store.metric_status_hist.observe({ flow: this.flowLabel }, 0);
+    this.store.metric_status_counter.inc({ flow: this.flowLabel, outcome: "failure" });
+    const latency = this.startTime != null ? (Date.now() - this.startTime) / 1000 : null;
+    appendCycleTrace(this.flowLabel, "failure", latency, this.cycleTrace);
     this.startTime = null;
     this.logger.error("Flow failed");
   }
@@ -207,46 +380,55 @@ export class FlowMetricRecorder {
   /// MANUAL FUNCTIONS
   /// Needed for recording based solly on onchain data
   public manualRecordStatus(status: Status, latencyTotalSec: number) {
-    store.metric_status.set({ flow: this.flowName }, status === Status.OK ? 1 : 0);
-    store.metric_status_hist.observe({ flow: this.flowName }, status === Status.OK ? 1 : 0);
-    store.metric_status_counter.inc({ flow: this.flowName, outcome: status === Status.OK ? "success" : "failure" });
     if (status === Status.OK) {
-      store.metric_latency_total.set({ flow: this.flowName }, latencyTotalSec);
+      this.store.metric_status.set({ flow: this.flowLabel }, 1);
+    } else {
+      this.recordStatusFailure();
+    }
+    this.store.metric_status_hist.observe({ flow: this.flowLabel }, status === Status.OK ? 1 : 0);
+    this.store.metric_status_counter.inc({
+      flow: this.flowLabel,
+      outcome: status === Status.OK ? "success" : "failure",
+    });
+    if (status === Status.OK) {
+      this.store.metric_latency_total.set({ flow: this.flowLabel }, latencyTotalSec);
       this._lastExecutionTotalLatency = latencyTotalSec;
+      this.store.last_success_ms.set(this.flowLabel, Date.now());
+      sendHeartbeat(this.flowName);
     }
   }
 
   public manualRecordStepCompletion(stepName: string, latencySec: number, stepEndSec: number) {
-    store.metric_latency.set({ flow: this.flowName, stage: stepName }, latencySec);
+    this.store.metric_latency.set({ flow: this.flowLabel, stage: stepName }, latencySec);
     this._lastStepLatency = latencySec;
-    store.metric_step_timestamp.set({ flow: this.flowName, step: stepName }, stepEndSec * 1000);
+    this.store.metric_step_timestamp.set({ flow: this.flowLabel, step: stepName }, stepEndSec * 1000);
     this.logger.info(`Step ${stepName} took ${latencySec} seconds`);
   }
 
   public manualRecordStepGas(stepName: string, gas: Numberish) {
-    store.metric_step_gas.set({ flow: this.flowName, step: stepName }, Number(gas));
+    this.store.metric_step_gas.set({ flow: this.flowLabel, step: stepName }, Number(gas));
   }
 
   public manualRecordStepGasPrice(stepName: string, price: Numberish) {
-    store.metric_step_gas_price.set({ flow: this.flowName, step: stepName }, Number(price));
+    this.store.metric_step_gas_price.set({ flow: this.flowLabel, step: stepName }, Number(price));
   }
 
   public manualRecordStepGasCost(stepName: string, cost: Numberish) {
-    store.metric_step_gas_cost.set({ flow: this.flowName, step: stepName }, Number(cost));
+    this.store.metric_step_gas_cost.set({ flow: this.flowLabel, step: stepName }, Number(cost));
   }
 
   public recordPreviousExecutionStatus(status: StatusNoSkip) {
     switch (status) {
       case Status.OK: {
-        store.metric_status.set({ flow: this.flowName }, 1);
-        store.metric_status_hist.observe({ flow: this.flowName }, 1);
-        store.metric_status_counter.inc({ flow: this.flowName, outcome: "success" });
+        this.store.metric_status.set({ flow: this.flowLabel }, 1);
+        this.store.metric_status_hist.observe({ flow: this.flowLabel }, 1);
+        this.store.metric_status_counter.inc({ flow: this.flowLabel, outcome: "success" });
         break;
       }
       case Status.FAIL: {
-        store.metric_status.set({ flow: this.flowName }, 0);
-        store.metric_status_hist.observe({ flow: this.flowName }, 0);
-        store.metric_status_counter.inc({ flow: this.flowName, outcome: "failure" });
+        this.store.metric_status.set({ flow: this.flowLabel }, 0);
+        this.store.metric_status_hist.observe({ flow: this.flowLabel }, 0);
+        this.store.metric_status_counter.inc({ flow: this.flowLabel, outcome: "failure" });
         break;
       }
       default: {
@@ -0,0 +1,70 @@
+import "dotenv/config";
+import { Gauge } from "prom-client";
+
+import { BaseFlow } from "./baseFlow";
+import { SEC } from "./utils";
+
+import type { Provider as EthersProvider } from "ethers";
+
+const FLOW_NAME = "get_logs";
+const GET_LOGS_BLOCKS = +(process.env.FLOW_GET_LOGS_BLOCKS ?? 100);
+/// upper bound of the queried range, so that a misconfiguration can't overload the node
+const GET_LOGS_MAX_BLOCKS = +(process.env.FLOW_GET_LOGS_MAX_BLOCKS ?? 10_000);
+const GET_LOGS_ADDRESS = process.env.FLOW_GET_LOGS_ADDRESS || undefined;
+const GET_LOGS_TOPIC = process.env.FLOW_GET_LOGS_TOPIC || undefined;
+
+/**
+ * Queries `eth_getLogs` over the most recent blocks, to monitor the log query path used by indexers. A slow or
+ * failing `eth_getLogs` is a common node degradation that transaction probes don't detect.
+ */
+export class GetLogsProbeFlow extends BaseFlow {
+  private metricResultCount: Gauge;
+  private blocks = Math.min(GET_LOGS_BLOCKS, GET_LOGS_MAX_BLOCKS);
+
+  constructor(
+    private provider: EthersProvider,
+    private intervalMs: number
+  ) {
+    super(FLOW_NAME);
+    this.metricResultCount = new Gauge({
+      name: "watchdog_get_logs_result_count",
+      help: "Number of logs returned by the last eth_getLogs query of the probe",
+    });
+    if (GET_LOGS_BLOCKS > GET_LOGS_MAX_BLOCKS) {
+      this.logger.warn(`FLOW_GET_LOGS_BLOCKS ${GET_LOGS_BLOCKS} capped to ${GET_LOGS_MAX_BLOCKS} blocks`);
+    }
+  }
+
+  public async run() {
+    while (true) {
+      const waitNextExecution = this.startCycle(this.intervalMs);
+
+      try {
+        this.metricRecorder.recordFlowStart();
+
+        const latestBlock = await this.provider.getBlockNumber();
+        const logs = await this.metricRecorder.stepExecution({
+          stepName: "get_logs",
+          stepTimeoutMs: 30 * SEC,
+          fn: () =>
+            this.provider.getLogs({
+              address: GET_LOGS_ADDRESS,
+              topics: GET_LOGS_TOPIC != null ? [GET_LOGS_TOPIC] : undefined,
+              fromBlock: Math.max(0, latestBlock - this.blocks + 1),
+              toBlock: latestBlock,
+            }),
+        });
+        this.logger.debug(`eth_getLogs returned ${logs.length} logs over ${this.blocks} blocks up to ${latestBlock}`);
+        this.metricResultCount.set(logs.length);
+
+        this.metricRecorder.recordFlowSuccess();
+        // eslint-disable-next-line @typescript-eslint/no-explicit-any
+      } catch (error: any) {
+        this.logger.error("eth_getLogs probe error: " + error?.message, error?.stack);
+        this.metricRecorder.recordFlowFailure();
+      }
+
+      await waitNextExecution();
+    }
+  }
+}
@@ -0,0 +1,49 @@
+import winston from "winston";
+
+import { ENVIRONMENT } from "./configs";
+import { SEC } from "./utils";
+
+/** Parses per-flow heartbeat URLs, e.g. `transfer=https://hc-ping.com/<uuid1>,deposit=https://hc-ping.com/<uuid2>`. */
+const parseFlowHeartbeatUrls = (flowHeartbeatUrls: string | undefined): Record<string, string> => {
+  const urls: Record<string, string> = {};
+  for (const entry of (flowHeartbeatUrls ?? "").split(",")) {
+    const separator = entry.indexOf("=");
+    if (separator === -1) continue;
+    const flowName = entry.slice(0, separator).trim();
+    const url = entry.slice(separator + 1).trim();
+    if (!flowName || !url) continue;
+    urls[flowName] = url;
+  }
+  return urls;
+};
+
+const HEARTBEAT_URL = process.env.HEARTBEAT_URL;
+const HEARTBEAT_URL_FLOWS = parseFlowHeartbeatUrls(process.env.HEARTBEAT_URL_FLOWS);
+/// pings of the same URL are throttled, fast flows (e.g. RPC test) would otherwise ping every second
+const HEARTBEAT_MIN_INTERVAL = +(process.env.HEARTBEAT_MIN_INTERVAL ?? 60 * SEC);
+const HEARTBEAT_TIMEOUT = 10 * SEC;
+
+/// last ping per URL, in ms
+const lastPingMs = new Map<string, number>();
+
+const ping = (url: string) => {
+  const now = Date.now();
+  if (now - (lastPingMs.get(url) ?? 0) < HEARTBEAT_MIN_INTERVAL) return;
+  lastPingMs.set(url, now);
+  // best-effort: a failing deadman service must not affect the flows
+  fetch(url, {
+    signal: AbortSignal.timeout(HEARTBEAT_TIMEOUT),
+    headers: ENVIRONMENT != null ? { "X-Watchdog-Environment": ENVIRONMENT } : undefined,
+  })
+    .then((response) => {
+      if (!response.ok) winston.warn(`Heartbeat ping failed with HTTP ${response.status}`);
+    })
+    .catch((error) => winston.warn(`Heartbeat ping failed: ${error?.message}`));
+};
+
+/** Pings the global heartbeat URL and the one of the flow (if configured) after a successful cycle of the flow. */
+export const sendHeartbeat = (flowName: string) => {
+  if (HEARTBEAT_URL != null) ping(HEARTBEAT_URL);
+  const flowUrl = HEARTBEAT_URL_FLOWS[flowName];
+  if (flowUrl != null) ping(flowUrl);
+};
@@ -0,0 +1,83 @@
+import { FetchRequest } from "ethers";
+import http from "http";
+import https from "https";
+import { Counter, Gauge } from "prom-client";
+import winston from "winston";
+
+import { SEC } from "./utils";
+
+/// max number of sockets kept open to all RPC endpoints together, bounds the file descriptors used
+const RPC_POOL_SIZE = +(process.env.RPC_POOL_SIZE ?? 50);
+/// idle keep-alive sockets are closed after this many ms
+const RPC_POOL_IDLE_TIMEOUT = +(process.env.RPC_POOL_IDLE_TIMEOUT ?? 30 * SEC);
+/// accept invalid (e.g. self-signed) certificates of all TLS connections, for internal and test environments only
+const RPC_ALLOW_INSECURE_TLS = process.env.RPC_ALLOW_INSECURE_TLS === "1";
+
+const metricConnectionsOpened = new Counter({
+  name: "watchdog_http_connections_opened",
+  help: "Number of connections opened by the RPC connection pool",
+  labelNames: ["protocol"],
+});
+const metricConnectionsReused = new Counter({
+  name: "watchdog_http_connections_reused",
+  help: "Number of RPC requests sent over a kept-alive connection instead of a new one",
+  labelNames: ["protocol"],
+});
+
+/** Counts the connections opened by the agent and the requests that reused a kept-alive one. */
+function instrumentAgent<T extends http.Agent>(agent: T, protocol: string): T {
+  // `createConnection` is the documented extension point of agents, but missing from their typings
+  const connectingAgent = agent as unknown as { createConnection: (...args: unknown[]) => unknown };
+  const createConnection = connectingAgent.createConnection.bind(agent);
+  connectingAgent.createConnection = (...args: unknown[]) => {
+    metricConnectionsOpened.inc({ protocol });
+    return createConnection(...args);
+  };
+  const reuseSocket = agent.reuseSocket.bind(agent);
+  agent.reuseSocket = (socket, request) => {
+    metricConnectionsReused.inc({ protocol });
+    reuseSocket(socket, request);
+  };
+  return agent;
+}
+
+/**
+ * Makes all ethers providers (L1 and L2) share one keep-alive connection pool instead of each request
+ * going through node's default agents. Must be called before the providers are used. Requests with an auth
+ * token (Prividium) go through the native `fetch`, which keeps its own pool.
+ * `RPC_ALLOW_INSECURE_TLS` is applied process wide, so that it also covers the native `fetch` (Prividium, heartbeats,
+ * top-ups, Safe API) and the WebSocket provider, which don't go through the pool.
+ */
+export function setupHttpPool() {
+  const options = {
+    keepAlive: true,
+    maxSockets: RPC_POOL_SIZE,
+    maxTotalSockets: RPC_POOL_SIZE,
+    timeout: RPC_POOL_IDLE_TIMEOUT,
+  };
+  const httpAgent = instrumentAgent(new http.Agent(options), "http");
+  if (RPC_ALLOW_INSECURE_TLS) {
+    winston.warn("RPC_ALLOW_INSECURE_TLS is set: the TLS certificates of all outbound connections are NOT verified");
+    process.env.NODE_TLS_REJECT_UNAUTHORIZED = "0";
+  }
+  const httpsAgent = instrumentAgent(
+    new https.Agent({ ...options, ...(RPC_ALLOW_INSECURE_TLS && { rejectUnauthorized: false }) }),
+    "https"
+  );
+  new Gauge({
+    name: "watchdog_http_connections_open",
+    help: "Number of connections of the RPC connection pool by `state`: `active` (serving a request) or `idle`",
+    labelNames: ["state"],
+    collect() {
+      const count = (sockets: NodeJS.ReadOnlyDict<unknown[]>) =>
+        Object.values(sockets).reduce((total, list) => total + (list?.length ?? 0), 0);
+      this.set({ state: "active" }, count(httpAgent.sockets) + count(httpsAgent.sockets));
+      this.set({ state: "idle" }, count(httpAgent.freeSockets) + count(httpsAgent.freeSockets));
+    },
+  });
+  const httpGetUrl = FetchRequest.createGetUrlFunc({ agent: httpAgent });
+  const httpsGetUrl = FetchRequest.createGetUrlFunc({ agent: httpsAgent });
+  FetchRequest.registerGetUrl((req, signal) =>
+    req.url.startsWith("https:") ? httpsGetUrl(req, signal) : httpGetUrl(req, signal)
+  );
+}
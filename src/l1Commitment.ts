@@ -0,0 +1,59 @@
+import "dotenv/config";
+import { Contract } from "ethers";
+import { Gauge } from "prom-client";
+
+import { BaseFlow } from "./baseFlow";
+import { SEC } from "./utils";
+
+import type { Provider } from "ethers";
+
+const FLOW_NAME = "l1_commitment";
+const DIAMOND_PROXY_ABI = ["function getTotalBatchesCommitted() view returns (uint256)"];
+
+/**
+ * Reads `getTotalBatchesCommitted` of the chain's diamond proxy on L1. Deposits and settlement depend on it, so a
+ * reverting or slow read is an early warning of a degraded settlement, complementing the L2-side batch progression.
+ */
+export class L1CommitmentFlow extends BaseFlow {
+  private metricTotalBatchesCommitted: Gauge;
+  private diamondProxy: Contract;
+
+  constructor(
+    l1Provider: Provider,
+    diamondProxyAddress: string,
+    private intervalMs: number
+  ) {
+    super(FLOW_NAME);
+    this.diamondProxy = new Contract(diamondProxyAddress, DIAMOND_PROXY_ABI, l1Provider);
+    this.metricTotalBatchesCommitted = new Gauge({
+      name: "watchdog_l1_total_batches_committed",
+      help: "Number of batches committed on L1, as returned by getTotalBatchesCommitted of the diamond proxy",
+    });
+  }
+
+  public async run() {
+    while (true) {
+      const waitNextExecution = this.startCycle(this.intervalMs);
+
+      try {
+        this.metricRecorder.recordFlowStart();
+
+        const totalBatchesCommitted: bigint = await this.metricRecorder.stepExecution({
+          stepName: "get_total_batches_committed",
+          stepTimeoutMs: 10 * SEC,
+          fn: () => this.diamondProxy.getTotalBatchesCommitted(),
+        });
+        this.logger.debug(`Total batches committed on L1: ${totalBatchesCommitted}`);
+        this.metricTotalBatchesCommitted.set(Number(totalBatchesCommitted));
+
+        this.metricRecorder.recordFlowSuccess();
+        // eslint-disable-next-line @typescript-eslint/no-explicit-any
+      } catch (error: any) {
+        this.logger.error("L1 commitment read error: " + error?.message, error?.stack);
+        this.metricRecorder.recordFlowFailure();
+      }
+
+      await waitNextExecution();
+    }
+  }
+}
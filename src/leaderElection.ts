@@ -0,0 +1,130 @@
+import { promises as fs } from "fs";
+import { hostname } from "os";
+import { Gauge } from "prom-client";
+import winston from "winston";
+
+import { SEC, timeoutPromise } from "./utils";
+
+export const LEADER_LEASE_TTL = +(process.env.LEADER_LEASE_TTL ?? 30 * SEC);
+/// time to wait after writing the lease before confirming we still hold it (last writer wins)
+const LEASE_SETTLE_MS = SEC;
+
+type Lease = { holder: string; expiresAt: number };
+
+/**
+ * Decides whether this replica may run the flows that send transactions from the watchdog wallet.
+ *
+ * - `leaseFile` set: replicas compete for a lease file on shared storage. The holder renews it every
+ *   third of the TTL, followers take over once it expires.
+ * - otherwise the role is static: `isStaticLeader` replicas send, the others only run read-only flows.
+ */
+export class LeaderElection {
+  private readonly holderId = `${hostname()}-${process.pid}`;
+  private leadership: Promise<void> | null = null;
+  private leader = false;
+  private metricIsLeader: Gauge;
+
+  constructor(
+    private leaseFile: string | undefined,
+    private isStaticLeader: boolean,
+    private leaseTtlMs: number
+  ) {
+    this.metricIsLeader = new Gauge({
+      name: "watchdog_is_leader",
+      help: "1 if this replica runs the transaction sending flows, 0 if it is a follower",
+    });
+    this.metricIsLeader.set(0);
+  }
+
+  /** Starts `run` once this replica becomes the leader. Followers never start it unless the leader goes away. */
+  public runWhenLeader(run: () => Promise<void>): Promise<void> {
+    return this.waitForLeadership().then(run);
+  }
+
+  public isLeader(): boolean {
+    return this.leader;
+  }
+
+  public waitForLeadership(): Promise<void> {
+    if (this.leadership == null) {
+      this.leadership = this.acquireLeadership();
+    }
+    return this.leadership;
+  }
+
+  private async acquireLeadership(): Promise<void> {
+    if (this.leaseFile == null) {
+      if (!this.isStaticLeader) {
+        winston.info("Replica configured as follower, transaction sending flows are disabled");
+        return new Promise<void>(() => {
+          // never resolves
+        });
+      }
+    } else {
+      const leaseFile = this.leaseFile;
+      winston.info(`Waiting for leader lease ${leaseFile} as ${this.holderId}`);
+      while (!(await this.tryAcquire(leaseFile))) {
+        await timeoutPromise(this.leaseTtlMs / 3);
+      }
+      winston.info(`Acquired leader lease ${leaseFile} as ${this.holderId}`);
+      this.renewLoop(leaseFile);
+    }
+    this.leader = true;
+    this.metricIsLeader.set(1);
+  }
+
+  private async tryAcquire(leaseFile: string): Promise<boolean> {
+    try {
+      const lease = await this.readLease(leaseFile);
+      if (lease != null && lease.holder !== this.holderId && lease.expiresAt > Date.now()) {
+        return false;
+      }
+      await this.writeLease(leaseFile);
+      // another replica may have raced us for the expired lease
+      await timeoutPromise(LEASE_SETTLE_MS);
+      return (await this.readLease(leaseFile))?.holder === this.holderId;
+      // eslint-disable-next-line @typescript-eslint/no-explicit-any
+    } catch (error: any) {
+      winston.error(`Failed to acquire leader lease ${leaseFile}: ${error?.message}`);
+      return false;
+    }
+  }
+
+  private async renewLoop(leaseFile: string) {
+    while (true) {
+      await timeoutPromise(this.leaseTtlMs / 3);
+      try {
+        const lease = await this.readLease(leaseFile);
+        if (lease != null && lease.holder !== this.holderId) {
+          // we must stop sending immediately, restarting as a follower is the simplest way to do so
+          winston.error(`Leader lease ${leaseFile} was taken over by ${lease.holder}, exiting`);
+          process.exit(1);
+        }
+        await this.writeLease(leaseFile);
+        // eslint-disable-next-line @typescript-eslint/no-explicit-any
+      } catch (error: any) {
+        winston.error(`Failed to renew leader lease ${leaseFile}: ${error?.message}`);
+      }
+    }
+  }
+
+  private async readLease(leaseFile: string): Promise<Lease | null> {
+    try {
+      return JSON.parse(await fs.readFile(leaseFile, "utf8")) as Lease;
+      // eslint-disable-next-line @typescript-eslint/no-explicit-any
+    } catch (error: any) {
+      if (error?.code === "ENOENT" || error instanceof SyntaxError) {
+        return null;
+      }
+      throw error;
+    }
+  }
+
+  private async writeLease(leaseFile: string) {
+    const lease: Lease = { holder: this.holderId, expiresAt: Date.now() + this.leaseTtlMs };
+    // write + rename so that other replicas never read a partially written lease
+    const tmpFile = `${leaseFile}.${this.holderId}.tmp`;
+    await fs.writeFile(tmpFile, JSON.stringify(lease));
+    await fs.rename(tmpFile, leaseFile);
+  }
+}
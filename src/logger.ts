@@ -2,8 +2,39 @@ import winston, { format, transports } from "winston";
 
 import type { Logform } from "winston";
 
-export const setupLogger = (environment: string | undefined, logLevel: string | undefined) => {
+/** Parses per-flow log levels, e.g. `deposit=debug,transfer=warn`. */
+const parseFlowLogLevels = (flowLogLevels: string | undefined): Record<string, string> => {
+  const levels: Record<string, string> = {};
+  for (const entry of (flowLogLevels ?? "").split(",")) {
+    const [flowName, level] = entry.split("=").map((part) => part.trim());
+    if (!flowName || !level) continue;
+    if (!(level in winston.config.npm.levels)) {
+      throw new Error(`Unknown log level "${level}" for flow "${flowName}"`);
+    }
+    levels[flowName] = level;
+  }
+  return levels;
+};
+
+export const setupLogger = (
+  environment: string | undefined,
+  logLevel: string | undefined,
+  flowLogLevels: string | undefined
+) => {
   const isProduction = environment === "production";
+  const npmLevels = winston.config.npm.levels;
+  const defaultLogLevel = logLevel || (isProduction ? "info" : "debug");
+  const levelOverrides = parseFlowLogLevels(flowLogLevels);
+  // the logger itself has to let through the most verbose level, the rest is filtered per flow
+  const maxLogLevel = [defaultLogLevel, ...Object.values(levelOverrides)].reduce((max, level) =>
+    npmLevels[level] > npmLevels[max] ? level : max
+  );
+
+  const flowLevelFormat = format((info) => {
+    const { flowName } = info;
+    const level = (typeof flowName === "string" ? levelOverrides[flowName] : undefined) ?? defaultLogLevel;
+    return npmLevels[info.level] <= npmLevels[level] ? info : false;
+  });
 
   const messageFlowNamePrefixFormat = format((info) => {
     const { flowName } = info;
@@ -22,6 +53,7 @@ export const setupLogger = (environment: string | undefined, logLevel: string |
 
   const loggerFormatters: Logform.Format[] = isProduction
     ? [
+        flowLevelFormat(),
         format.timestamp({
           format: () => new Date().toISOString(),
         }),
@@ -30,6 +62,7 @@ export const setupLogger = (environment: string | undefined, logLevel: string |
         format.json(),
       ]
     : [
+        flowLevelFormat(),
         format.timestamp({
           format: "DD/MM/YYYY HH:mm:ss.SSS",
         }),
@@ -38,10 +71,8 @@ export const setupLogger = (environment: string | undefined, logLevel: string |
         format.simple(),
       ];
 
-  const defaultLogLevel = isProduction ? "info" : "debug";
-
   winston.configure({
-    level: logLevel || defaultLogLevel,
+    level: maxLogLevel,
     transports: [
       new transports.Console({
         format: format.combine(...loggerFormatters),
@@ -1,30 +1,61 @@
 import "dotenv/config";
+// must be loaded before any flow module reads its configuration
+import "./configFile";
 import { ethers } from "ethers";
 import express from "express";
 import { collectDefaultMetrics, register } from "prom-client";
 import winston from "winston";
-import { Provider } from "zksync-ethers";
 import { IL1SharedBridge__factory } from "zksync-ethers/build/typechain";
 
-import { SETTLEMENT_DEADLINE } from "./configs";
+import { BatchProgressionFlow } from "./batchProgression";
+import { ClockSkewFlow } from "./clockSkew";
+import {
+  BASE_TOKEN_DECIMALS,
+  ENVIRONMENT,
+  ESTIMATE_PERIOD_SECONDS,
+  SETTLEMENT_DEADLINE,
+  STARTUP_DELAY_SECONDS,
+  STARTUP_WAIT_FOR_SYNC,
+  STARTUP_WARMUP,
+} from "./configs";
 import { DepositFlow } from "./deposit";
+import { ABI_MISMATCH_HINT, applyBridgehubAbi, isAbiMismatch } from "./depositBase";
 import { DepositUserFlow } from "./depositUsers";
-import { recordWalletInfo } from "./flowMetric";
+import { drainOnExit, getDrainOnExitArg } from "./drain";
+import { FeeHistoryProbeFlow } from "./feeHistoryProbe";
+import { FeeLadderFlow } from "./feeLadder";
+import { recordStartupDuration, recordWalletInfo } from "./flowMetric";
+import { GetLogsProbeFlow } from "./getLogsProbe";
+import { setupHttpPool } from "./httpPool";
+import { L1CommitmentFlow } from "./l1Commitment";
+import { LEADER_LEASE_TTL, LeaderElection } from "./leaderElection";
 import { Mutex } from "./lock";
 import { setupLogger } from "./logger";
+import { startMetricsFileExport } from "./metricsFile";
+import { runMetricsOnly } from "./metricsOnly";
+import { NegativeProbeFlow } from "./negativeProbe";
+import { PaymasterRejectionProbeFlow } from "./paymasterRejectionProbe";
+import { printConfig } from "./printConfig";
 import { PrividiumFlow } from "./prividium";
 import { runSiweFlow } from "./prividiumAuth";
+import { ProtocolVersionFlow } from "./protocolVersion";
+import { RpcCapabilityFlow } from "./rpcCapability";
 import { LoggingEthersJsonRpcProvider, LoggingZkSyncProvider } from "./rpcLoggingProvider";
 import { RpcTestFlow } from "./rpcTest";
+import { SafeProposalFlow } from "./safeProposal";
 import { SettlementFlow } from "./settlement";
 import { SimpleTxFlow } from "./transfer";
-import { SEC, unwrap } from "./utils";
-import { createEthersSigner, createZkSyncWallet } from "./walletFactory";
+import { TransferEstimateFlow } from "./transferEstimate";
+import { MIN, SEC, timeoutPromise, unwrap } from "./utils";
+import { createEthersSigner, createZkSyncWallet, getWalletKey, readWalletKeyFromStdin } from "./walletFactory";
 import { WithdrawalFlow } from "./withdrawal";
+import { WithdrawalE2eFlow } from "./withdrawalE2e";
 import { WithdrawalFinalizeFlow } from "./withdrawalFinalize";
 
 import type { PrividiumTokenStore } from "./prividiumAuth";
+import type { IL1SharedBridge } from "zksync-ethers/build/typechain";
 import type { JsonRpcApiProviderOptions } from "ethers";
+import type { Wallet } from "zksync-ethers";
 
 function getProviderOptions(opts?: JsonRpcApiProviderOptions): JsonRpcApiProviderOptions {
   return {
@@ -33,9 +64,216 @@ function getProviderOptions(opts?: JsonRpcApiProviderOptions): JsonRpcApiProvide
   };
 }
 
+function createL1Provider() {
+  return new LoggingZkSyncProvider(unwrap(process.env.CHAIN_L1_RPC_URL), undefined, getProviderOptions());
+}
+
+/// L2 WebSocket endpoint the transfer flow detects the inclusion of its transactions with, instead of polling
+function createWsProvider(): ethers.WebSocketProvider | null {
+  if (!process.env.CHAIN_WS_URL) return null;
+  winston.info("Transfer confirmations subscribe to new heads of the WebSocket endpoint");
+  return new ethers.WebSocketProvider(process.env.CHAIN_WS_URL);
+}
+
+/// Shuts down on a fatal flow error through the SIGTERM handler (drain on exit, shutdown timeout), exiting non-zero
+// eslint-disable-next-line @typescript-eslint/no-explicit-any
+function exitOnFatalError(error: any) {
+  winston.error("Fatal error, exiting: " + error?.message, error?.stack);
+  process.exitCode = 1;
+  if (process.listenerCount("SIGTERM") === 0) process.exit(1);
+  process.kill(process.pid, "SIGTERM");
+}
+
+/// Refuses to start against an unexpected network (`EXPECTED_CHAIN_ID`), as the watchdog spends funds
+async function checkExpectedChainId(l2Provider: LoggingZkSyncProvider) {
+  const expectedChainIds = (process.env.EXPECTED_CHAIN_ID ?? "")
+    .split(",")
+    .map((chainId) => chainId.trim())
+    .filter((chainId) => chainId.length > 0)
+    .map((chainId) => BigInt(chainId));
+  if (expectedChainIds.length === 0) return;
+  const chainId = (await l2Provider.getNetwork()).chainId;
+  if (!expectedChainIds.includes(chainId)) {
+    winston.error(`L2 chain id ${chainId} is not one of EXPECTED_CHAIN_ID ${expectedChainIds.join(", ")}, exiting`);
+    process.exit(1);
+  }
+}
+
+/// Refuses to start when the wallet is a smart account, the transactions signed for it as an EOA would all be rejected
+async function checkNotSmartAccount(l2Provider: LoggingZkSyncProvider, address: string) {
+  const code = await l2Provider.getCode(address);
+  // EIP-7702 delegated EOAs have a delegation designator as code, but still sign as EOAs
+  if (code === "0x" || code.startsWith("0xef0100")) return;
+  winston.error(
+    `Wallet ${address} is a smart account (it has contract code), which the watchdog can't send transactions for. ` +
+      "Configure the key of an EOA, exiting"
+  );
+  process.exit(1);
+}
+
+/// In single node dev setups L1 and L2 are the same chain, so there's nothing to bridge and deposits are skipped
+async function isL1SameChain(l2Provider: LoggingZkSyncProvider, l1Provider: LoggingZkSyncProvider): Promise<boolean> {
+  const sameChain =
+    process.env.CHAIN_L1_RPC_URL === process.env.CHAIN_RPC_URL ||
+    (await l1Provider.getNetwork()).chainId === (await l2Provider.getNetwork()).chainId;
+  if (sameChain) {
+    winston.info("L1 and L2 are the same chain, skipping deposit flows");
+  }
+  return sameChain;
+}
+
+/// Readiness gate: a node that is still syncing would make every flow fail right after startup
+async function waitForNodeSync(provider: ethers.JsonRpcProvider) {
+  while (true) {
+    try {
+      const syncing = await provider.send("eth_syncing", []);
+      if (syncing === false) {
+        winston.info("L2 node is synced");
+        return;
+      }
+      winston.info(`L2 node is syncing: ${JSON.stringify(syncing)}`);
+      // eslint-disable-next-line @typescript-eslint/no-explicit-any
+    } catch (error: any) {
+      winston.warn(`eth_syncing failed: ${error?.message}`);
+    }
+    await timeoutPromise(5 * SEC);
+  }
+}
+
+/// startup resolutions of the deposit contracts are retried, a flaky endpoint would otherwise crash the watchdog
+const STARTUP_RESOLUTION_RETRY_LIMIT = +(process.env.STARTUP_RESOLUTION_RETRY_LIMIT ?? 5);
+
+type DepositContracts = {
+  chainId: bigint;
+  baseToken: string;
+  sharedBridge: IL1SharedBridge;
+  zkChainAddress: string;
+};
+
+/**
+ * Resolves the bridgehub and the contracts the deposit flows are constructed with, retrying with exponential
+ * backoff (1 second doubling). Returns `null` if it still fails, so that the caller can do without the deposit flows.
+ */
+async function resolveDepositContracts(walletDeposit: Wallet, isZKsyncOS: boolean): Promise<DepositContracts | null> {
+  applyBridgehubAbi(walletDeposit);
+  for (let attempt = 0; ; attempt++) {
+    try {
+      const chainId = (await walletDeposit.provider.getNetwork()).chainId;
+      const baseToken = await walletDeposit.getBaseToken();
+      if (isZKsyncOS) {
+        const bridgehub = await walletDeposit.getBridgehubContract();
+        const assetRouter = await bridgehub.sharedBridge();
+        const sharedBridge = IL1SharedBridge__factory.connect(assetRouter, walletDeposit._signerL1());
+        return { chainId, baseToken, sharedBridge, zkChainAddress: await bridgehub.getHyperchain(chainId) };
+      }
+      const sharedBridge = (await walletDeposit.getL1BridgeContracts()).shared;
+      const zkChainAddress = await walletDeposit._providerL2().getMainContractAddress();
+      return { chainId, baseToken, sharedBridge, zkChainAddress };
+      // eslint-disable-next-line @typescript-eslint/no-explicit-any
+    } catch (error: any) {
+      if (attempt >= STARTUP_RESOLUTION_RETRY_LIMIT) {
+        winston.error(`Failed to resolve the deposit contracts after ${attempt + 1} attempts: ${error?.message}`);
+        if (isAbiMismatch(error)) winston.error(ABI_MISMATCH_HINT);
+        return null;
+      }
+      winston.warn(`Failed to resolve the deposit contracts, retrying: ${error?.message}`);
+      await timeoutPromise(SEC * 2 ** attempt);
+    }
+  }
+}
+
+/**
+ * Resolves the static values of the chain the flows depend on (chain ids, contract addresses, base token), which the
+ * providers cache, so that the first cycles don't pay for their resolution. The protocol version is only logged.
+ * The providers are the ones the flows are constructed with.
+ */
+async function warmup(
+  l2Provider: LoggingZkSyncProvider,
+  l1Provider: LoggingZkSyncProvider | null,
+  isZKsyncOS: boolean
+) {
+  const start = Date.now();
+  const l2ChainId = (await l2Provider.getNetwork()).chainId;
+  const bridgehub = await l2Provider.getBridgehubContractAddress();
+  let summary = `L2 chain id ${l2ChainId}, bridgehub ${bridgehub}`;
+  if (l1Provider != null) {
+    summary += `, L1 chain id ${(await l1Provider.getNetwork()).chainId}`;
+    // read from the bridgehub on L1
+    l2Provider.setL1Provider(l1Provider);
+    summary += `, base token ${await l2Provider.getBaseTokenContractAddress()}`;
+  }
+  // zks_ methods, not available on ZKsync OS
+  if (!isZKsyncOS) {
+    summary += `, main contract ${await l2Provider.getMainContractAddress()}`;
+    const protocolVersion = await l2Provider.send("zks_getProtocolVersion", []);
+    summary += `, protocol version ${protocolVersion?.minorVersion ?? protocolVersion?.version_id}`;
+  }
+  winston.info(`Warmup done in ${Date.now() - start} ms: ${summary}`);
+}
+
+/**
+ * `deposit-once` command: sends a single deposit through the deposit flow and prints the result, then exits.
+ * With `--dry-run` only the estimation is performed and printed, nothing is approved or sent.
+ */
+async function depositOnce(
+  l2Provider: LoggingZkSyncProvider,
+  l1Provider: LoggingZkSyncProvider | null,
+  l2EthersProvider: ethers.Provider,
+  isZKsyncOS: boolean,
+  dryRun: boolean
+) {
+  try {
+    if (l1Provider == null) throw new Error("CHAIN_L1_RPC_URL is not set");
+    l2Provider.setL1Provider(l1Provider);
+    l2Provider.setIsZKsyncOS(isZKsyncOS);
+    const walletDeposit = await createZkSyncWallet(getWalletKey("DEPOSIT"), l2Provider, l1Provider);
+    const contracts = await resolveDepositContracts(walletDeposit, isZKsyncOS);
+    if (contracts == null) {
+      throw new Error("Failed to resolve the deposit contracts");
+    }
+    const { chainId, baseToken, sharedBridge, zkChainAddress } = contracts;
+    const depositFlow = new DepositFlow(
+      walletDeposit,
+      sharedBridge,
+      zkChainAddress,
+      chainId,
+      baseToken,
+      l2EthersProvider,
+      isZKsyncOS,
+      0
+    );
+    if (dryRun) {
+      const { value, baseCost, l2GasLimit, l1GasLimit, l1MaxFeePerGas } = await depositFlow.estimateDepositOnce();
+      console.log(`Value: ${value} (${ethers.formatEther(value)} ETH)`);
+      console.log(`Base cost: ${baseCost} (${ethers.formatUnits(baseCost, BASE_TOKEN_DECIMALS)} base token)`);
+      console.log(`L2 gas limit: ${l2GasLimit}`);
+      console.log(`L1 gas limit: ${l1GasLimit} at max fee per gas ${ethers.formatUnits(l1MaxFeePerGas, "gwei")} gwei`);
+      process.exit(0);
+    }
+    const { l1TxHash, baseCost, l2GasLimit } = await depositFlow.depositOnce();
+    console.log(`L1 tx hash: ${l1TxHash}`);
+    console.log(`Base cost: ${baseCost} (${ethers.formatUnits(baseCost, BASE_TOKEN_DECIMALS)} base token)`);
+    console.log(`L2 gas limit: ${l2GasLimit}`);
+    process.exit(0);
+    // eslint-disable-next-line @typescript-eslint/no-explicit-any
+  } catch (error: any) {
+    console.error(`Deposit failed: ${error?.message}`);
+    process.exit(1);
+  }
+}
+
 const main = async () => {
-  setupLogger(process.env.NODE_ENV, process.env.LOG_LEVEL);
+  setupLogger(process.env.NODE_ENV, process.env.LOG_LEVEL, process.env.LOG_LEVEL_FLOWS);
+  setupHttpPool();
+  if (process.env.WALLET_KEY_STDIN === "1") {
+    await readWalletKeyFromStdin();
+  }
+  const drainAddress = getDrainOnExitArg(process.argv.slice(2));
+  // only the leader runs flows that send transactions from the watchdog wallet
+  const leaderElection = new LeaderElection(process.env.LEADER_LEASE_FILE, process.env.LEADER !== "0", LEADER_LEASE_TTL);
   const l2Provider = new LoggingZkSyncProvider(unwrap(process.env.CHAIN_RPC_URL), undefined, getProviderOptions());
+  // one L1 provider for the warmup and all the flows, so that what it resolves once (e.g. the chain id) is cached
+  const sharedL1Provider = process.env.CHAIN_L1_RPC_URL ? createL1Provider() : null;
 
   // For ZKsync OS chains we cannot use `LoggingZkSyncProvider` for getting tx receipt
   // because format of L2 to L1 logs is different. So we create a separate ethers provider for that.
@@ -47,6 +285,22 @@ const main = async () => {
     })
   );
   const zkos_mode = process.env.ZKOS_MODE === "1";
+  const wsProvider = createWsProvider();
+  await checkExpectedChainId(l2Provider);
+  if (process.argv[2] === "deposit-once") {
+    await depositOnce(l2Provider, sharedL1Provider, l2EthersProvider, zkos_mode, process.argv.includes("--dry-run"));
+  }
+
+  if (STARTUP_DELAY_SECONDS > 0) {
+    winston.info(`Waiting ${STARTUP_DELAY_SECONDS} seconds before starting flows`);
+    await timeoutPromise(STARTUP_DELAY_SECONDS * SEC);
+  }
+  if (STARTUP_WAIT_FOR_SYNC) {
+    await waitForNodeSync(l2Provider);
+  }
+  if (STARTUP_WARMUP) {
+    await warmup(l2Provider, sharedL1Provider, zkos_mode);
+  }
 
   let enabledFlows = 0;
 
@@ -57,7 +311,7 @@ const main = async () => {
     if (process.env.FLOW_PRIVIDIUM_ENABLE === "1") {
       const prividiumApiUrl = unwrap(process.env.FLOW_PRIVIDIUM_API_URL);
       const prividiumDomain = unwrap(process.env.FLOW_PRIVIDIUM_DOMAIN);
-      const wallet = await createEthersSigner(unwrap(process.env.WALLET_KEY));
+      const wallet = await createEthersSigner(getWalletKey("DEFAULT"));
       const prividiumTokenStore: PrividiumTokenStore = { token: null };
 
       await runSiweFlow(wallet, prividiumApiUrl, prividiumDomain, prividiumTokenStore);
@@ -66,197 +320,413 @@ const main = async () => {
 
       // Prividium flow (refreshes auth token and records metrics)
       const prividiumIntervalMs = +(process.env.FLOW_PRIVIDIUM_INTERVAL ?? SEC);
-      new PrividiumFlow(wallet, prividiumDomain, prividiumApiUrl, prividiumIntervalMs, prividiumTokenStore).run();
+      new PrividiumFlow(
+        wallet,
+        prividiumDomain,
+        prividiumApiUrl,
+        prividiumIntervalMs,
+        prividiumTokenStore
+      ).runSupervised();
       enabledFlows++;
     }
 
-    const wallet = await createEthersSigner(unwrap(process.env.WALLET_KEY), l2Provider);
+    const wallet = await createEthersSigner(getWalletKey("DEFAULT"), l2Provider);
     const l2WalletLock = new Mutex();
+    if (drainAddress != null) {
+      drainOnExit(wallet, drainAddress, l2WalletLock, leaderElection);
+    }
 
     const walletAddress = await wallet.getAddress();
     winston.info(
-      `Wallet ${walletAddress} L2 balance is ${ethers.formatEther(await l2Provider.getBalance(walletAddress))}`
+      `Wallet ${walletAddress} L2 balance is ${ethers.formatUnits(await l2Provider.getBalance(walletAddress), BASE_TOKEN_DECIMALS)}`
     );
     recordWalletInfo(walletAddress);
+    await checkNotSmartAccount(l2Provider, walletAddress);
     if (process.env.FLOW_TRANSFER_ENABLE === "1") {
-      new SimpleTxFlow(
+      const transferFlow = new SimpleTxFlow(
         l2Provider,
         wallet,
         l2WalletLock,
-        void 0,
+        [],
         +unwrap(process.env.FLOW_TRANSFER_INTERVAL),
-        l2EthersProvider
-      ).run();
+        l2EthersProvider,
+        wsProvider
+      );
+      leaderElection.runWhenLeader(() => transferFlow.runSupervised()).catch(exitOnFatalError);
       enabledFlows++;
     }
 
-    if (process.env.FLOW_DEPOSIT_ENABLE === "1") {
-      const l1Provider = new Provider(unwrap(process.env.CHAIN_L1_RPC_URL), undefined, getProviderOptions());
-      l2Provider.setL1Provider(l1Provider);
+    // Transfer estimate flow (estimate-only checks in between the transfer sends)
+    if (ESTIMATE_PERIOD_SECONDS != null) {
+      new TransferEstimateFlow(l2Provider, walletAddress, ESTIMATE_PERIOD_SECONDS * SEC).runSupervised();
+      enabledFlows++;
+    }
 
-      const walletDeposit = await createZkSyncWallet(unwrap(process.env.WALLET_KEY), l2Provider, l1Provider);
-      const chainId = (await walletDeposit.provider.getNetwork()).chainId;
-      const baseToken = await walletDeposit.getBaseToken();
+    // Fee ladder flow (experimental, several transfers per cycle at stepped fees)
+    if (process.env.FLOW_FEE_LADDER_ENABLE === "1") {
+      const feeLadderIntervalMs = +(process.env.FLOW_FEE_LADDER_INTERVAL ?? 10 * MIN);
+      const feeLadderFlow = new FeeLadderFlow(wallet, l2EthersProvider, l2WalletLock, feeLadderIntervalMs);
+      leaderElection.runWhenLeader(() => feeLadderFlow.runSupervised()).catch(exitOnFatalError);
+      enabledFlows++;
+    }
 
-      const bridgehub = await walletDeposit.getBridgehubContract();
-      const assetRouter = await bridgehub.sharedBridge();
-      const sharedBridge = IL1SharedBridge__factory.connect(assetRouter, walletDeposit._signerL1());
-      const zkChainAddress = await bridgehub.getHyperchain(chainId);
+    if (process.env.FLOW_DEPOSIT_ENABLE === "1" && !(await isL1SameChain(l2Provider, unwrap(sharedL1Provider)))) {
+      const l1Provider = unwrap(sharedL1Provider);
+      l2Provider.setL1Provider(l1Provider);
 
-      new DepositFlow(
-        walletDeposit,
-        sharedBridge,
-        zkChainAddress,
-        chainId,
-        baseToken,
-        l2EthersProvider,
-        true,
-        +unwrap(process.env.FLOW_DEPOSIT_INTERVAL)
-      ).run();
-      enabledFlows++;
+      const walletDeposit = await createZkSyncWallet(getWalletKey("DEPOSIT"), l2Provider, l1Provider);
+      recordWalletInfo(walletDeposit.address);
+      const contracts = await resolveDepositContracts(walletDeposit, true);
+      if (contracts == null) {
+        winston.error("Not starting the deposit flow");
+      } else {
+        const depositFlow = new DepositFlow(
+          walletDeposit,
+          contracts.sharedBridge,
+          contracts.zkChainAddress,
+          contracts.chainId,
+          contracts.baseToken,
+          l2EthersProvider,
+          true,
+          +unwrap(process.env.FLOW_DEPOSIT_INTERVAL)
+        );
+        leaderElection.runWhenLeader(() => depositFlow.runSupervised()).catch(exitOnFatalError);
+        enabledFlows++;
+      }
     }
 
     if (process.env.FLOW_WITHDRAWAL_ENABLE === "1") {
-      const wallet = await createZkSyncWallet(unwrap(process.env.WALLET_KEY), l2Provider);
-      new WithdrawalFlow(
+      const wallet = await createZkSyncWallet(getWalletKey("DEFAULT"), l2Provider);
+      const withdrawalFlow = new WithdrawalFlow(
         wallet,
         void 0,
         true,
         l2WalletLock,
         +unwrap(process.env.FLOW_WITHDRAWAL_INTERVAL),
         l2EthersProvider
-      ).run();
+      );
+      leaderElection.runWhenLeader(() => withdrawalFlow.runSupervised()).catch(exitOnFatalError);
       enabledFlows++;
     }
     // RPC Test flow (eth_blockNumber)
     if (process.env.FLOW_RPC_TEST_ENABLE !== "0") {
       const rpcTestIntervalMs = +(process.env.FLOW_RPC_TEST_INTERVAL ?? SEC);
-      new RpcTestFlow(l2Provider, rpcTestIntervalMs).run();
+      new RpcTestFlow(l2Provider, rpcTestIntervalMs).runSupervised();
+      enabledFlows++;
+    }
+
+    // RPC capability flow (which of the RPC methods the flows depend on are served)
+    if (process.env.FLOW_RPC_CAPABILITY_ENABLE === "1") {
+      const rpcCapabilityIntervalMs = +(process.env.FLOW_RPC_CAPABILITY_INTERVAL ?? 10 * MIN);
+      new RpcCapabilityFlow(l2Provider, true, rpcCapabilityIntervalMs).runSupervised();
+      enabledFlows++;
+    }
+
+    // Negative probe flow (estimation of an invalid transaction must fail)
+    if (process.env.FLOW_NEGATIVE_PROBE_ENABLE === "1") {
+      const negativeProbeIntervalMs = +(process.env.FLOW_NEGATIVE_PROBE_INTERVAL ?? 60 * SEC);
+      new NegativeProbeFlow(l2Provider, walletAddress, negativeProbeIntervalMs).runSupervised();
+      enabledFlows++;
+    }
+
+    // Clock skew flow (latest block timestamp vs local clock)
+    if (process.env.FLOW_CLOCK_SKEW_ENABLE === "1") {
+      const clockSkewIntervalMs = +(process.env.FLOW_CLOCK_SKEW_INTERVAL ?? 60 * SEC);
+      new ClockSkewFlow(l2Provider, clockSkewIntervalMs).runSupervised();
+      enabledFlows++;
+    }
+
+    // eth_getLogs probe flow (log query path over recent blocks)
+    if (process.env.FLOW_GET_LOGS_ENABLE === "1") {
+      const getLogsIntervalMs = +(process.env.FLOW_GET_LOGS_INTERVAL ?? 60 * SEC);
+      new GetLogsProbeFlow(l2Provider, getLogsIntervalMs).runSupervised();
+      enabledFlows++;
+    }
+
+    // eth_feeHistory probe flow (fee oracle used by wallets)
+    if (process.env.FLOW_FEE_HISTORY_ENABLE === "1") {
+      const feeHistoryIntervalMs = +(process.env.FLOW_FEE_HISTORY_INTERVAL ?? 60 * SEC);
+      new FeeHistoryProbeFlow(l2Provider, feeHistoryIntervalMs).runSupervised();
       enabledFlows++;
     }
 
     // Settlement flow
     if (process.env.FLOW_SETTLEMENT_ENABLE === "1") {
-      const l1Provider = new Provider(unwrap(process.env.CHAIN_L1_RPC_URL), undefined, getProviderOptions());
+      const l1Provider = unwrap(sharedL1Provider);
       const settlementIntervalMs = +(process.env.FLOW_SETTLEMENT_INTERVAL ?? SEC);
-      new SettlementFlow(l2Provider, l1Provider, settlementIntervalMs, SETTLEMENT_DEADLINE).run();
+      new SettlementFlow(l2Provider, l1Provider, settlementIntervalMs, SETTLEMENT_DEADLINE).runSupervised();
+      enabledFlows++;
+    }
+
+    // Safe proposal flow
+    if (process.env.FLOW_SAFE_PROPOSAL_ENABLE === "1") {
+      const signer = await createEthersSigner(getWalletKey("DEFAULT"));
+      const chainId = (await l2Provider.getNetwork()).chainId;
+      new SafeProposalFlow(
+        signer,
+        chainId,
+        unwrap(process.env.FLOW_SAFE_PROPOSAL_TX_SERVICE_URL),
+        unwrap(process.env.FLOW_SAFE_PROPOSAL_SAFE_ADDRESS),
+        +unwrap(process.env.FLOW_SAFE_PROPOSAL_INTERVAL)
+      ).runSupervised();
       enabledFlows++;
     }
   } else {
-    const wallet = await createZkSyncWallet(unwrap(process.env.WALLET_KEY), l2Provider);
-    const paymasterAddress = process.env.PAYMASTER_ADDRESS;
+    const wallet = await createZkSyncWallet(getWalletKey("DEFAULT"), l2Provider);
+    // several paymasters are probed in rotation by the transfer flow, other flows use the first one
+    const paymasterAddresses = (process.env.PAYMASTER_ADDRESS ?? "")
+      .split(",")
+      .map((address) => address.trim())
+      .filter((address) => address !== "");
+    const paymasterAddress = paymasterAddresses[0] as string | undefined;
     const l2WalletLock = new Mutex();
+    if (drainAddress != null) {
+      drainOnExit(wallet, drainAddress, l2WalletLock, leaderElection);
+    }
 
     winston.info(
-      `Wallet ${wallet.address} L2 balance is ${ethers.formatEther(await l2Provider.getBalance(wallet.address))}`
+      `Wallet ${wallet.address} L2 balance is ${ethers.formatUnits(await l2Provider.getBalance(wallet.address), BASE_TOKEN_DECIMALS)}`
     );
     recordWalletInfo(wallet.address);
+    await checkNotSmartAccount(l2Provider, wallet.address);
     if (process.env.FLOW_TRANSFER_ENABLE === "1") {
-      new SimpleTxFlow(
+      const transferFlow = new SimpleTxFlow(
         l2Provider,
         wallet,
         l2WalletLock,
-        paymasterAddress,
-        +unwrap(process.env.FLOW_TRANSFER_INTERVAL)
-      ).run();
+        paymasterAddresses,
+        +unwrap(process.env.FLOW_TRANSFER_INTERVAL),
+        null,
+        wsProvider
+      );
+      leaderElection.runWhenLeader(() => transferFlow.runSupervised()).catch(exitOnFatalError);
+      enabledFlows++;
+    }
+
+    // Transfer estimate flow (estimate-only checks in between the transfer sends)
+    if (ESTIMATE_PERIOD_SECONDS != null) {
+      new TransferEstimateFlow(l2Provider, wallet.address, ESTIMATE_PERIOD_SECONDS * SEC).runSupervised();
       enabledFlows++;
     }
 
-    if (process.env.FLOW_DEPOSIT_ENABLE === "1" || process.env.FLOW_DEPOSIT_USER_ENABLE === "1") {
-      const l1Provider = new Provider(unwrap(process.env.CHAIN_L1_RPC_URL), undefined, getProviderOptions());
+    // Fee ladder flow (experimental, several transfers per cycle at stepped fees)
+    if (process.env.FLOW_FEE_LADDER_ENABLE === "1") {
+      const feeLadderIntervalMs = +(process.env.FLOW_FEE_LADDER_INTERVAL ?? 10 * MIN);
+      const feeLadderFlow = new FeeLadderFlow(wallet, l2Provider, l2WalletLock, feeLadderIntervalMs);
+      leaderElection.runWhenLeader(() => feeLadderFlow.runSupervised()).catch(exitOnFatalError);
+      enabledFlows++;
+    }
+
+    if (
+      (process.env.FLOW_DEPOSIT_ENABLE === "1" || process.env.FLOW_DEPOSIT_USER_ENABLE === "1") &&
+      !(await isL1SameChain(l2Provider, unwrap(sharedL1Provider)))
+    ) {
+      const l1Provider = unwrap(sharedL1Provider);
       l2Provider.setL1Provider(l1Provider);
-      const walletDeposit = await createZkSyncWallet(unwrap(process.env.WALLET_KEY), l2Provider, l1Provider);
-      const l1BridgeContracts = await walletDeposit.getL1BridgeContracts();
-      const chainId = (await walletDeposit.provider.getNetwork()).chainId;
-      const baseToken = await walletDeposit.getBaseToken();
-      const zkChainAddress = await walletDeposit._providerL2().getMainContractAddress();
+      const walletDeposit = await createZkSyncWallet(getWalletKey("DEPOSIT"), l2Provider, l1Provider);
+      recordWalletInfo(walletDeposit.address);
+      const contracts = await resolveDepositContracts(walletDeposit, false);
       winston.info(
         `Wallet ${walletDeposit.address} L1 balance is ${ethers.formatEther(await l1Provider.getBalance(walletDeposit.address))}`
       );
-      if (process.env.FLOW_DEPOSIT_ENABLE === "1") {
-        new DepositFlow(
+      if (contracts == null) {
+        winston.error("Not starting the deposit flows");
+      }
+      if (contracts != null && process.env.FLOW_DEPOSIT_ENABLE === "1") {
+        const depositFlow = new DepositFlow(
           walletDeposit,
-          l1BridgeContracts.shared,
-          zkChainAddress,
-          chainId,
-          baseToken,
+          contracts.sharedBridge,
+          contracts.zkChainAddress,
+          contracts.chainId,
+          contracts.baseToken,
           l2EthersProvider,
           false,
           +unwrap(process.env.FLOW_DEPOSIT_INTERVAL)
-        ).run();
+        );
+        leaderElection.runWhenLeader(() => depositFlow.runSupervised()).catch(exitOnFatalError);
         enabledFlows++;
       }
-      if (process.env.FLOW_DEPOSIT_USER_ENABLE === "1") {
-        new DepositUserFlow(
+      if (contracts != null && process.env.FLOW_DEPOSIT_USER_ENABLE === "1") {
+        const depositUserFlow = new DepositUserFlow(
           walletDeposit,
-          l1BridgeContracts.shared,
-          zkChainAddress,
-          chainId,
-          baseToken,
+          contracts.sharedBridge,
+          contracts.zkChainAddress,
+          contracts.chainId,
+          contracts.baseToken,
           l2EthersProvider,
           false,
           +unwrap(process.env.FLOW_DEPOSIT_USER_INTERVAL),
           +unwrap(process.env.FLOW_DEPOSIT_USER_TX_TRIGGER_DELAY)
-        ).run();
+        );
+        leaderElection.runWhenLeader(() => depositUserFlow.runSupervised()).catch(exitOnFatalError);
         enabledFlows++;
       }
     }
     if (process.env.FLOW_WITHDRAWAL_ENABLE === "1") {
-      new WithdrawalFlow(
+      const withdrawalFlow = new WithdrawalFlow(
         wallet,
         paymasterAddress,
         false,
         l2WalletLock,
         +unwrap(process.env.FLOW_WITHDRAWAL_INTERVAL),
         l2EthersProvider
-      ).run();
+      );
+      leaderElection.runWhenLeader(() => withdrawalFlow.runSupervised()).catch(exitOnFatalError);
       enabledFlows++;
     }
     if (process.env.FLOW_WITHDRAWAL_FINALIZE_ENABLE === "1") {
       // We need a wallet with both L2 and L1 providers for withdrawal finalization
-      // Create a new wallet with L1 provider
-      const l1ProviderForWithdrawal = new LoggingZkSyncProvider(
-        unwrap(process.env.CHAIN_L1_RPC_URL),
-        undefined,
-        getProviderOptions()
-      );
       const walletForWithdrawals = await createZkSyncWallet(
-        unwrap(process.env.WALLET_KEY),
+        getWalletKey("DEFAULT"),
         l2Provider,
-        l1ProviderForWithdrawal
+        unwrap(sharedL1Provider)
       );
 
       new WithdrawalFinalizeFlow(
         walletForWithdrawals,
         false,
         +unwrap(process.env.FLOW_WITHDRAWAL_FINALIZE_INTERVAL)
-      ).run();
+      ).runSupervised();
+      enabledFlows++;
+    }
+    if (process.env.FLOW_WITHDRAWAL_E2E_ENABLE === "1") {
+      // finalizability is checked on L1, so the wallet needs an L1 provider as well
+      const walletForWithdrawalE2e = await createZkSyncWallet(
+        getWalletKey("DEFAULT"),
+        l2Provider,
+        unwrap(sharedL1Provider)
+      );
+      const withdrawalE2eIntervalMs = +(process.env.FLOW_WITHDRAWAL_E2E_INTERVAL ?? 5 * MIN);
+      const withdrawalE2eFlow = new WithdrawalE2eFlow(walletForWithdrawalE2e, l2WalletLock, withdrawalE2eIntervalMs);
+      leaderElection.runWhenLeader(() => withdrawalE2eFlow.runSupervised()).catch(exitOnFatalError);
       enabledFlows++;
     }
 
     // RPC Test flow (eth_blockNumber)
     if (process.env.FLOW_RPC_TEST_ENABLE !== "0") {
       const rpcTestIntervalMs = +(process.env.FLOW_RPC_TEST_INTERVAL ?? 1000);
-      new RpcTestFlow(l2Provider, rpcTestIntervalMs).run();
+      new RpcTestFlow(l2Provider, rpcTestIntervalMs).runSupervised();
+      enabledFlows++;
+    }
+
+    // RPC capability flow (which of the RPC methods the flows depend on are served)
+    if (process.env.FLOW_RPC_CAPABILITY_ENABLE === "1") {
+      const rpcCapabilityIntervalMs = +(process.env.FLOW_RPC_CAPABILITY_INTERVAL ?? 10 * MIN);
+      new RpcCapabilityFlow(l2Provider, false, rpcCapabilityIntervalMs).runSupervised();
+      enabledFlows++;
+    }
+
+    // Negative probe flow (estimation of an invalid transaction must fail)
+    if (process.env.FLOW_NEGATIVE_PROBE_ENABLE === "1") {
+      const negativeProbeIntervalMs = +(process.env.FLOW_NEGATIVE_PROBE_INTERVAL ?? 60 * SEC);
+      new NegativeProbeFlow(l2Provider, wallet.address, negativeProbeIntervalMs).runSupervised();
+      enabledFlows++;
+    }
+
+    // Paymaster rejection probe flow (estimation of a transaction the paymaster must not sponsor must fail)
+    if (process.env.FLOW_PAYMASTER_REJECTION_ENABLE === "1") {
+      const rejectingPaymaster = process.env.FLOW_PAYMASTER_REJECTION_ADDRESS || paymasterAddress;
+      if (rejectingPaymaster == null) {
+        winston.warn("FLOW_PAYMASTER_REJECTION_ENABLE is set, but no paymaster is configured. Not starting the flow");
+      } else {
+        const paymasterRejectionIntervalMs = +(process.env.FLOW_PAYMASTER_REJECTION_INTERVAL ?? 60 * SEC);
+        new PaymasterRejectionProbeFlow(
+          l2Provider,
+          wallet.address,
+          rejectingPaymaster,
+          paymasterRejectionIntervalMs
+        ).runSupervised();
+        enabledFlows++;
+      }
+    }
+
+    // Clock skew flow (latest block timestamp vs local clock)
+    if (process.env.FLOW_CLOCK_SKEW_ENABLE === "1") {
+      const clockSkewIntervalMs = +(process.env.FLOW_CLOCK_SKEW_INTERVAL ?? 60 * SEC);
+      new ClockSkewFlow(l2Provider, clockSkewIntervalMs).runSupervised();
+      enabledFlows++;
+    }
+
+    // eth_getLogs probe flow (log query path over recent blocks)
+    if (process.env.FLOW_GET_LOGS_ENABLE === "1") {
+      const getLogsIntervalMs = +(process.env.FLOW_GET_LOGS_INTERVAL ?? 60 * SEC);
+      new GetLogsProbeFlow(l2Provider, getLogsIntervalMs).runSupervised();
+      enabledFlows++;
+    }
+
+    // eth_feeHistory probe flow (fee oracle used by wallets)
+    if (process.env.FLOW_FEE_HISTORY_ENABLE === "1") {
+      const feeHistoryIntervalMs = +(process.env.FLOW_FEE_HISTORY_INTERVAL ?? 60 * SEC);
+      new FeeHistoryProbeFlow(l2Provider, feeHistoryIntervalMs).runSupervised();
+      enabledFlows++;
+    }
+
+    // Protocol version flow (zks_getProtocolVersion, not available on ZKsync OS)
+    if (process.env.FLOW_PROTOCOL_VERSION_ENABLE === "1") {
+      const protocolVersionIntervalMs = +(process.env.FLOW_PROTOCOL_VERSION_INTERVAL ?? 5 * MIN);
+      new ProtocolVersionFlow(l2Provider, protocolVersionIntervalMs).runSupervised();
       enabledFlows++;
     }
 
     // Settlement flow
     if (process.env.FLOW_SETTLEMENT_ENABLE === "1") {
-      const l1Provider = new Provider(unwrap(process.env.CHAIN_L1_RPC_URL), undefined, getProviderOptions());
+      const l1Provider = unwrap(sharedL1Provider);
       const settlementIntervalMs = +(process.env.FLOW_SETTLEMENT_INTERVAL ?? 1000);
-      new SettlementFlow(l2Provider, l1Provider, settlementIntervalMs, SETTLEMENT_DEADLINE).run();
+      new SettlementFlow(l2Provider, l1Provider, settlementIntervalMs, SETTLEMENT_DEADLINE).runSupervised();
+      enabledFlows++;
+    }
+
+    // Batch progression flow (zks_getL1BatchDetails)
+    if (process.env.FLOW_BATCH_PROGRESSION_ENABLE === "1") {
+      const batchProgressionIntervalMs = +(process.env.FLOW_BATCH_PROGRESSION_INTERVAL ?? 30 * SEC);
+      new BatchProgressionFlow(l2Provider, batchProgressionIntervalMs).runSupervised();
+      enabledFlows++;
+    }
+
+    // L1 commitment flow (getTotalBatchesCommitted of the diamond proxy, found via zks_getMainContract)
+    if (process.env.FLOW_L1_COMMITMENT_ENABLE === "1") {
+      const l1Provider = unwrap(sharedL1Provider);
+      const diamondProxyAddress =
+        process.env.FLOW_L1_COMMITMENT_DIAMOND_PROXY || (await l2Provider.getMainContractAddress());
+      const l1CommitmentIntervalMs = +(process.env.FLOW_L1_COMMITMENT_INTERVAL ?? 60 * SEC);
+      new L1CommitmentFlow(l1Provider, diamondProxyAddress, l1CommitmentIntervalMs).runSupervised();
+      enabledFlows++;
+    }
+
+    // Safe proposal flow
+    if (process.env.FLOW_SAFE_PROPOSAL_ENABLE === "1") {
+      const signer = await createEthersSigner(getWalletKey("DEFAULT"));
+      const chainId = (await l2Provider.getNetwork()).chainId;
+      new SafeProposalFlow(
+        signer,
+        chainId,
+        unwrap(process.env.FLOW_SAFE_PROPOSAL_TX_SERVICE_URL),
+        unwrap(process.env.FLOW_SAFE_PROPOSAL_SAFE_ADDRESS),
+        +unwrap(process.env.FLOW_SAFE_PROPOSAL_INTERVAL)
+      ).runSupervised();
       enabledFlows++;
     }
   }
-  winston.info(`Enabled ${enabledFlows} flows`);
+  // a slow startup usually points at RPC connectivity problems
+  recordStartupDuration(process.uptime());
+  winston.info(`Enabled ${enabledFlows} flows in ${process.uptime().toFixed(1)} seconds`);
   if (enabledFlows === 0) {
     winston.error("No flows enabled");
     process.exit(1);
   }
 };
 
+if (process.argv.includes("--print-config")) {
+  printConfig();
+}
+
+if (ENVIRONMENT != null) {
+  register.setDefaultLabels({ env: ENVIRONMENT });
+}
 collectDefaultMetrics();
+if (process.env.METRICS_FILE) {
+  startMetricsFileExport(process.env.METRICS_FILE);
+}
 
 const app = express();
 
@@ -269,6 +739,15 @@ app.get("/metrics", async (_req, res) => {
   }
 });
 
-app.listen(+(process.env.METRICS_PORT ?? 8080), "0.0.0.0");
+const metricsPort = +(process.env.METRICS_PORT ?? 8080);
+// `0` disables the HTTP exporter, e.g. when only the metrics file can be used
+if (metricsPort !== 0) {
+  app.listen(metricsPort, "0.0.0.0");
+}
 
-main();
+if (process.env.METRICS_ONLY === "1") {
+  setupLogger(process.env.NODE_ENV, process.env.LOG_LEVEL, process.env.LOG_LEVEL_FLOWS);
+  runMetricsOnly();
+} else {
+  main();
+}
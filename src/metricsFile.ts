@@ -0,0 +1,45 @@
+import { appendFile, rename, stat } from "fs/promises";
+import { register } from "prom-client";
+import winston from "winston";
+
+import { SEC } from "./utils";
+
+const METRICS_FILE_INTERVAL = +(process.env.METRICS_FILE_INTERVAL ?? 60 * SEC);
+/// once exceeded, the file is rotated to `<path>.1` (replacing the previous one)
+const METRICS_FILE_MAX_BYTES = +(process.env.METRICS_FILE_MAX_BYTES ?? 100 * 1024 * 1024);
+
+/** Adds the scrape timestamp (in ms) to every sample of the Prometheus text exposition. */
+const withTimestamps = (metrics: string, timestampMs: number): string =>
+  metrics
+    .split("\n")
+    .map((line) => (line.length === 0 || line.startsWith("#") ? line : `${line} ${timestampMs}`))
+    .join("\n");
+
+const fileSize = async (path: string): Promise<number> => {
+  try {
+    return (await stat(path)).size;
+    // eslint-disable-next-line @typescript-eslint/no-explicit-any
+  } catch (error: any) {
+    if (error?.code === "ENOENT") return 0;
+    throw error;
+  }
+};
+
+const exportMetrics = async (path: string) => {
+  const chunk = withTimestamps(await register.metrics(), Date.now()) + "\n";
+  if ((await fileSize(path)) + chunk.length > METRICS_FILE_MAX_BYTES) {
+    await rename(path, `${path}.1`);
+  }
+  await appendFile(path, chunk);
+};
+
+/**
+ * Periodically appends all metrics to a local file, for deployments that can't be scraped over HTTP.
+ * The file is shipped out of band, failures to write it are logged and don't affect the flows.
+ */
+export function startMetricsFileExport(path: string) {
+  winston.info(`Writing metrics to ${path} every ${METRICS_FILE_INTERVAL / SEC} seconds`);
+  setInterval(() => {
+    exportMetrics(path).catch((error) => winston.error(`Failed to write metrics to ${path}: ${error?.message}`));
+  }, METRICS_FILE_INTERVAL);
+}
@@ -0,0 +1,51 @@
+import winston from "winston";
+
+import {
+  FlowMetricRecorder,
+  recordL1Balances,
+  recordL2BaseTokenBalance,
+  recordStartupDuration,
+  recordWalletInfo,
+  Status,
+} from "./flowMetric";
+import { SEC, timeoutPromise } from "./utils";
+
+/// flows whose metrics are synthesized, with the stages they report
+const SYNTHETIC_FLOWS: Record<string, string[]> = {
+  transfer: ["estimation", "signing", "send", "execution"],
+  deposit: ["estimation", "send", "l1_execution", "l1_confirmations", "l2_execution"],
+  withdrawal: ["estimation", "send", "execution"],
+  rpc_test: ["rpc_test"],
+  settlement: ["settlement"],
+};
+const METRICS_ONLY_INTERVAL = +(process.env.METRICS_ONLY_INTERVAL ?? 10 * SEC);
+
+/**
+ * `METRICS_ONLY` mode: emits static values for the shared metrics of the main flows (status, latencies, gas,
+ * balances) without any RPC endpoint or wallet, to validate dashboards and alerting rules end to end. The values
+ * are refreshed every `METRICS_ONLY_INTERVAL`, so that staleness based alerts stay quiet. The metrics specific to a
+ * flow are registered by its constructor, which needs a chain, so they aren't emitted.
+ */
+export async function runMetricsOnly(): Promise<never> {
+  winston.warn("METRICS_ONLY is set, emitting synthetic metrics without connecting to any chain");
+  const recorders = Object.entries(SYNTHETIC_FLOWS).map(
+    ([flowName, stages]) => [new FlowMetricRecorder(flowName, winston.child({ flowName })), stages] as const
+  );
+  recordWalletInfo("0x0000000000000000000000000000000000000000");
+  recordStartupDuration(process.uptime());
+  while (true) {
+    const nowSec = Date.now() / 1000;
+    for (const [recorder, stages] of recorders) {
+      for (const stage of stages) {
+        recorder.manualRecordStepCompletion(stage, 1, nowSec);
+        recorder.manualRecordStepGas(stage, 21_000);
+        recorder.manualRecordStepGasPrice(stage, 1_000_000_000);
+        recorder.manualRecordStepGasCost(stage, 21_000 * 1_000_000_000);
+      }
+      recorder.manualRecordStatus(Status.OK, stages.length);
+    }
+    recordL2BaseTokenBalance(10n ** 18n);
+    recordL1Balances(10n ** 18n, 10n ** 18n);
+    await timeoutPromise(METRICS_ONLY_INTERVAL);
+  }
+}
@@ -0,0 +1,70 @@
+import "dotenv/config";
+import { Gauge } from "prom-client";
+
+import { BaseFlow } from "./baseFlow";
+import { isTransportError } from "./rpcLoggingProvider";
+import { SEC } from "./utils";
+
+import type { Provider as EthersProvider } from "ethers";
+
+const FLOW_NAME = "negative_probe";
+
+/**
+ * Verifies that the node rejects invalid transactions, not just that it accepts valid ones: estimates a
+ * transfer of more than the wallet balance, which must fail. Nothing is ever sent.
+ */
+export class NegativeProbeFlow extends BaseFlow {
+  private metricCorrect: Gauge;
+
+  constructor(
+    private provider: EthersProvider,
+    private address: string,
+    private intervalMs: number
+  ) {
+    super(FLOW_NAME);
+    this.metricCorrect = new Gauge({
+      name: "watchdog_negative_probe_correct",
+      help: "1 if the node correctly rejected the invalid transaction at estimation, 0 if it unexpectedly accepted it",
+    });
+  }
+
+  public async run() {
+    while (true) {
+      const waitNextExecution = this.startCycle(this.intervalMs);
+
+      try {
+        this.metricRecorder.recordFlowStart();
+
+        await this.metricRecorder.stepExecution({
+          stepName: "estimation",
+          stepTimeoutMs: 10 * SEC,
+          fn: async () => {
+            const balance = await this.provider.getBalance(this.address);
+            try {
+              await this.provider.estimateGas({ from: this.address, to: this.address, value: balance + 1n });
+              // eslint-disable-next-line @typescript-eslint/no-explicit-any
+            } catch (error: any) {
+              // an unreachable node doesn't tell anything about its validation
+              if (isTransportError(error)) {
+                throw error;
+              }
+              this.logger.debug("Invalid transaction correctly rejected: " + error?.message);
+              this.metricCorrect.set(1);
+              return;
+            }
+            this.metricCorrect.set(0);
+            throw new Error(`Estimation of a transfer exceeding the balance (${balance} wei) unexpectedly succeeded`);
+          },
+        });
+
+        this.metricRecorder.recordFlowSuccess();
+        // eslint-disable-next-line @typescript-eslint/no-explicit-any
+      } catch (error: any) {
+        this.logger.error("Negative probe error: " + error?.message, error?.stack);
+        this.metricRecorder.recordFlowFailure();
+      }
+
+      await waitNextExecution();
+    }
+  }
+}
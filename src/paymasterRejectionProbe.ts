@@ -0,0 +1,88 @@
+import "dotenv/config";
+import { Gauge } from "prom-client";
+import { utils } from "zksync-ethers";
+
+import { BaseFlow } from "./baseFlow";
+import { isPaymasterError } from "./errors";
+import { isTransportError } from "./rpcLoggingProvider";
+import { SEC } from "./utils";
+
+import type { Provider } from "zksync-ethers";
+
+const FLOW_NAME = "paymaster_rejection";
+/// the transaction the paymaster is configured not to sponsor, e.g. a call to a contract outside its allowlist
+const REJECTION_TO = process.env.FLOW_PAYMASTER_REJECTION_TO || undefined;
+const REJECTION_DATA = process.env.FLOW_PAYMASTER_REJECTION_DATA || "0x";
+const REJECTION_INNER_INPUT = process.env.FLOW_PAYMASTER_REJECTION_INNER_INPUT || "0x";
+
+/**
+ * Verifies that the paymaster enforces its sponsoring policy, not just that it sponsors valid transactions:
+ * estimates a transaction the paymaster must reject, which has to fail at paymaster validation. Nothing is
+ * ever sent, so a paymaster that wrongly accepts it isn't charged.
+ */
+export class PaymasterRejectionProbeFlow extends BaseFlow {
+  private metricCorrectRejection: Gauge;
+
+  constructor(
+    private provider: Provider,
+    private address: string,
+    private paymasterAddress: string,
+    private intervalMs: number
+  ) {
+    super(FLOW_NAME);
+    this.metricCorrectRejection = new Gauge({
+      name: "watchdog_paymaster_correct_rejection",
+      help: "1 if the paymaster correctly rejected the transaction it must not sponsor, 0 if it accepted it",
+      labelNames: ["paymaster"],
+    });
+  }
+
+  public async run() {
+    const paymaster = this.paymasterAddress;
+    while (true) {
+      const waitNextExecution = this.startCycle(this.intervalMs);
+
+      try {
+        this.metricRecorder.recordFlowStart();
+
+        await this.metricRecorder.stepExecution({
+          stepName: "estimation",
+          stepTimeoutMs: 10 * SEC,
+          fn: async () => {
+            const paymasterParams = utils.getPaymasterParams(paymaster, {
+              type: "General",
+              innerInput: REJECTION_INNER_INPUT,
+            });
+            try {
+              await this.provider.estimateGas({
+                from: this.address,
+                to: REJECTION_TO ?? this.address,
+                data: REJECTION_DATA,
+                customData: { gasPerPubdata: utils.DEFAULT_GAS_PER_PUBDATA_LIMIT, paymasterParams },
+              });
+              // eslint-disable-next-line @typescript-eslint/no-explicit-any
+            } catch (error: any) {
+              // an unreachable node or an unrelated rejection doesn't tell anything about the paymaster policy
+              if (isTransportError(error) || !isPaymasterError(error)) {
+                throw error;
+              }
+              this.logger.debug("Transaction correctly rejected by the paymaster: " + error?.message);
+              this.metricCorrectRejection.set({ paymaster }, 1);
+              return;
+            }
+            this.metricCorrectRejection.set({ paymaster }, 0);
+            throw new Error(`Paymaster ${paymaster} unexpectedly accepted the transaction it must reject`);
+          },
+        });
+
+        this.metricRecorder.recordFlowSuccess();
+        // eslint-disable-next-line @typescript-eslint/no-explicit-any
+      } catch (error: any) {
+        this.logger.error("Paymaster rejection probe error: " + error?.message, error?.stack);
+        this.metricRecorder.recordFlowFailure();
+      }
+
+      await waitNextExecution();
+    }
+  }
+}
@@ -0,0 +1,123 @@
+import { unwrap } from "./utils";
+import { isKmsKey, isRemoteSignerKey } from "./walletFactory";
+
+/// environment variables that configure the watchdog
+const CONFIG_PREFIXES = [
+  "CHAIN_",
+  "FLOW_",
+  "WALLET_KEY",
+  "PAYMASTER_",
+  "LOG_LEVEL",
+  "LEADER",
+  "L2_",
+  "SETTLEMENT_",
+  "STARTUP_",
+  "OVERRIDE_",
+  "MAX_LOGS_",
+  "METRICS_",
+  "NODE_ENV",
+  "ZKOS_MODE",
+  "PRE_V26_BRIDGES",
+  "CONFIRMATION_MODE",
+  "CONFIRMATION_BLOCK_TAG",
+  "FINALIZATION_",
+  "SCHEDULE_MODE",
+  "RPC_POOL_",
+  "RPC_ALLOW_INSECURE_TLS",
+  "MIN_PRIORITY_FEE_",
+  "STATE_FILE",
+  "HEARTBEAT_",
+  "ESTIMATE_BLOCK_TAG",
+  "ESTIMATE_SEND_DELAY_MS",
+  "ESTIMATE_PERIOD_SECONDS",
+  "MAX_MEMPOOL_WAIT_",
+  "RATE_LIMIT_",
+  "DEPOSIT_L1_CONFIRMATIONS",
+  "DEPOSIT_L2_RECIPIENT",
+  "DEPOSIT_BRIDGEHUB_ABI_PATH",
+  "EXIT_ON_REVERT",
+  "TOPUP_",
+  "TRACE_CSV_PATH",
+  "SHUTDOWN_TIMEOUT_SECONDS",
+  "EXPECTED_CHAIN_ID",
+  "LOOP_RESTART_",
+  "BASE_TOKEN_DECIMALS",
+  "STATUS_GRACE_PERIOD",
+  "REMOTE_SIGNER_",
+  "MAX_TOTAL_SPEND_",
+  "ENVIRONMENT",
+  "FIXED_GAS_LIMIT",
+];
+const NUMERIC_NAME =
+  /_(INTERVAL|TIMEOUT|LIMIT|DEADLINE|TTL|SECONDS|PERCENT|THRESHOLD|CONFIRMATIONS|GWEI|WEI|PORT|BLOCKS|DELAY|BYTES|PERIOD)$/;
+const REQUIRED_NAMES = ["CHAIN_RPC_URL"];
+
+const SECRET_NAME = /_(TOKEN|SECRET|KEY)(_|$)/;
+const URL_NAME = /_URLS?(_|$)/;
+
+/// RPC and webhook URLs often embed API keys in the userinfo, path or query, only the scheme and host are kept
+const redactUrl = (value: string): string => {
+  try {
+    const url = new URL(value);
+    const origin = `${url.protocol}//${url.host}`;
+    return value.replace(/\/+$/, "") === origin ? value : `${origin}/<redacted>`;
+  } catch {
+    return "<redacted>";
+  }
+};
+
+const redact = (name: string, value: string): string => {
+  if (name.startsWith("WALLET_KEY")) {
+    // KMS key names and remote signer addresses only point to the key, so they are safe to print
+    return isKmsKey(value) || isRemoteSignerKey(value) ? value : "<redacted>";
+  }
+  if (SECRET_NAME.test(name)) return "<redacted>";
+  if (URL_NAME.test(name)) {
+    // some of them are comma-separated lists, of failover endpoints or of `flow=url` pairs
+    return value
+      .split(",")
+      .map((entry) => {
+        const [, label, url] = unwrap(/^([\w-]+=)?(.*)$/.exec(entry.trim()));
+        return (label ?? "") + redactUrl(url);
+      })
+      .join(",");
+  }
+  return value;
+};
+
+/** Returns the problems found in the configuration, empty if it's valid. */
+export function validateConfig(env: NodeJS.ProcessEnv): string[] {
+  const errors = REQUIRED_NAMES.filter((name) => !env[name]).map((name) => `${name} is not set`);
+  const hasSharedKey = env.WALLET_KEY || env.WALLET_KEY_STDIN === "1";
+  if (!hasSharedKey && !(env.WALLET_KEY_DEFAULT && env.WALLET_KEY_DEPOSIT)) {
+    errors.push("WALLET_KEY is not set (it's required unless both WALLET_KEY_DEFAULT and WALLET_KEY_DEPOSIT are)");
+  }
+  for (const [name, value] of Object.entries(env)) {
+    if (name.startsWith("WALLET_KEY") && value && isRemoteSignerKey(value) && !env.REMOTE_SIGNER_URL) {
+      errors.push(`${name} is an address, which requires REMOTE_SIGNER_URL to be set`);
+    }
+    if (NUMERIC_NAME.test(name) && value !== undefined && value !== "" && !Number.isFinite(Number(value))) {
+      errors.push(`${name}=${value} is not a number`);
+    }
+  }
+  return errors;
+}
+
+/**
+ * Prints the effective configuration (after `.env` and the config file are applied) with the wallet key, the
+ * secrets and all but the scheme and host of the URLs redacted, and exits. Exits with 1 if the configuration is
+ * invalid.
+ */
+export function printConfig(env: NodeJS.ProcessEnv = process.env): never {
+  const names = Object.keys(env)
+    .filter((name) => CONFIG_PREFIXES.some((prefix) => name.startsWith(prefix)))
+    .sort();
+  for (const name of names) {
+    console.log(`${name}=${redact(name, env[name] ?? "")}`);
+  }
+  const errors = validateConfig(env);
+  for (const error of errors) {
+    console.error(`Invalid configuration: ${error}`);
+  }
+  process.exit(errors.length > 0 ? 1 : 0);
+}
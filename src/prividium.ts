@@ -1,6 +1,6 @@
 import { BaseFlow } from "./baseFlow";
 import { runSiweFlow } from "./prividiumAuth";
-import { SEC, timeoutPromise } from "./utils";
+import { SEC } from "./utils";
 
 import type { PrividiumTokenStore } from "./prividiumAuth";
 import type { Signer } from "ethers";
@@ -20,7 +20,7 @@ export class PrividiumFlow extends BaseFlow {
 
   public async run() {
     while (true) {
-      const nextExecutionWait = timeoutPromise(this.intervalMs);
+      const waitNextExecution = this.startCycle(this.intervalMs);
 
       try {
         this.metricRecorder.recordFlowStart();
@@ -45,7 +45,7 @@ export class PrividiumFlow extends BaseFlow {
         this.metricRecorder.recordFlowFailure();
       }
 
-      await nextExecutionWait;
+      await waitNextExecution();
     }
   }
 }
@@ -0,0 +1,73 @@
+import "dotenv/config";
+import { Gauge } from "prom-client";
+
+import { BaseFlow } from "./baseFlow";
+import { SEC } from "./utils";
+
+import type { Provider } from "zksync-ethers";
+
+const FLOW_NAME = "protocol_version";
+
+/**
+ * Reads the protocol version the chain is running via `zks_getProtocolVersion`, so that behavior changes
+ * can be attributed to protocol upgrades. Upgrades are logged, unexpected ones can be alerted on.
+ */
+export class ProtocolVersionFlow extends BaseFlow {
+  private metricProtocolVersion: Gauge;
+  private version: string | null = null;
+
+  constructor(
+    private provider: Provider,
+    private intervalMs: number
+  ) {
+    super(FLOW_NAME);
+    this.metricProtocolVersion = new Gauge({
+      name: "watchdog_l2_protocol_version",
+      help: "Protocol (minor) version of the chain as the `version` label, the value is always 1",
+      labelNames: ["version"],
+    });
+  }
+
+  public async run() {
+    while (true) {
+      const waitNextExecution = this.startCycle(this.intervalMs);
+
+      try {
+        this.metricRecorder.recordFlowStart();
+
+        const version = await this.metricRecorder.stepExecution({
+          stepName: "get_protocol_version",
+          stepTimeoutMs: 10 * SEC,
+          fn: async () => {
+            const protocolVersion = await this.provider.send("zks_getProtocolVersion", []);
+            // `version_id` is deprecated in favor of `minorVersion`, older nodes only report the former
+            const version = protocolVersion?.minorVersion ?? protocolVersion?.version_id;
+            if (version == null) {
+              throw new Error(`Unexpected zks_getProtocolVersion response ${JSON.stringify(protocolVersion)}`);
+            }
+            return String(version);
+          },
+        });
+        if (version !== this.version) {
+          if (this.version != null) {
+            this.logger.warn(`Protocol version changed from ${this.version} to ${version}`);
+          } else {
+            this.logger.info(`Protocol version is ${version}`);
+          }
+          // only the current version is reported
+          this.metricProtocolVersion.reset();
+          this.metricProtocolVersion.set({ version }, 1);
+          this.version = version;
+        }
+
+        this.metricRecorder.recordFlowSuccess();
+        // eslint-disable-next-line @typescript-eslint/no-explicit-any
+      } catch (error: any) {
+        this.logger.error("Protocol version error: " + error?.message, error?.stack);
+        this.metricRecorder.recordFlowFailure();
+      }
+
+      await waitNextExecution();
+    }
+  }
+}
@@ -0,0 +1,52 @@
+import { Gauge } from "prom-client";
+import winston from "winston";
+
+import { SEC, timeoutPromise } from "./utils";
+
+/// pause of the sends after a rate limited request, when the endpoint doesn't tell it with `Retry-After`
+const RATE_LIMIT_COOLDOWN = +(process.env.RATE_LIMIT_COOLDOWN ?? 30 * SEC);
+/// upper bound of the pause, so that a bogus `Retry-After` can't stop the watchdog for good
+const RATE_LIMIT_COOLDOWN_MAX = +(process.env.RATE_LIMIT_COOLDOWN_MAX ?? 10 * 60 * SEC);
+
+/// end of the current cooldown in ms, in the past when not cooling down
+let cooldownUntil = 0;
+
+new Gauge({
+  name: "watchdog_rpc_cooldown_seconds",
+  help: "Seconds left of the cooldown after the RPC endpoint rate limited a request (sends are paused), 0 if none",
+  collect() {
+    this.set(Math.max(0, cooldownUntil - Date.now()) / 1000);
+  },
+});
+
+/** `Retry-After` of the HTTP response of a failed request in ms, either delay seconds or an HTTP date. */
+// eslint-disable-next-line @typescript-eslint/no-explicit-any
+function getRetryAfter(error: any): number | null {
+  const retryAfter = error?.response?.headers?.["retry-after"];
+  if (typeof retryAfter !== "string" || retryAfter.trim() === "") return null;
+  if (/^\d+$/.test(retryAfter.trim())) return +retryAfter * SEC;
+  const date = Date.parse(retryAfter);
+  return Number.isNaN(date) ? null : Math.max(0, date - Date.now());
+}
+
+/** Starts (or extends) the cooldown after a request was rate limited, respecting its `Retry-After` if any. */
+// eslint-disable-next-line @typescript-eslint/no-explicit-any
+export function enterRateLimitCooldown(error: any) {
+  const cooldown = Math.min(getRetryAfter(error) ?? RATE_LIMIT_COOLDOWN, RATE_LIMIT_COOLDOWN_MAX);
+  const until = Date.now() + cooldown;
+  if (until <= cooldownUntil) return;
+  if (cooldownUntil < Date.now()) {
+    winston.warn(`RPC request rate limited, pausing sends for ${cooldown / SEC} seconds`);
+  }
+  cooldownUntil = until;
+}
+
+/**
+ * Waits until the rate limit cooldown is over. Flows sending transactions call it before each attempt, so that
+ * the watchdog doesn't keep hammering an endpoint under quota pressure.
+ */
+export async function waitForRateLimitCooldown() {
+  while (cooldownUntil > Date.now()) {
+    await timeoutPromise(cooldownUntil - Date.now());
+  }
+}
@@ -0,0 +1,69 @@
+import { ethers } from "ethers";
+
+import type { TypedDataDomain, TypedDataField } from "ethers";
+
+/**
+ * An ethers v6 Signer that delegates signing to a remote JSON-RPC signer
+ * (e.g. an HSM-backed signer running next to the watchdog).
+ *
+ * The signer must hold the key of `address` and implement:
+ * - `eth_signTransaction` for plain L1/L2 transactions
+ * - `personal_sign` for messages
+ * - `eth_signTypedData_v4` for typed data, including EIP-712 ZkSync transactions
+ *
+ * With `REMOTE_SIGNER_URL` set, the WALLET_KEY env var should be the address of the signer's key.
+ */
+export class RemoteSigner extends ethers.AbstractSigner {
+  private readonly signerRpc: ethers.JsonRpcProvider;
+
+  constructor(
+    readonly address: string,
+    private readonly signerUrl: string,
+    provider?: ethers.Provider | null
+  ) {
+    super(provider);
+    // the signer is not a chain node, don't let ethers probe it for a network
+    this.signerRpc = new ethers.JsonRpcProvider(signerUrl, undefined, { staticNetwork: true });
+  }
+
+  async getAddress(): Promise<string> {
+    return this.address;
+  }
+
+  // ---- signing ---------------------------------------------------------------
+
+  async signTransaction(tx: ethers.TransactionRequest): Promise<string> {
+    const resolved = await ethers.resolveProperties({
+      ...tx,
+      to: tx.to == null ? tx.to : ethers.resolveAddress(tx.to, this.provider),
+      from: this.address,
+    });
+    const rpcTx = this.signerRpc.getRpcTransaction(resolved);
+    const signed: string = await this.signerRpc.send("eth_signTransaction", [rpcTx]);
+    const from = ethers.Transaction.from(signed).from;
+    if (from == null || from.toLowerCase() !== this.address.toLowerCase()) {
+      throw new Error(`Remote signer signed the transaction as ${from}, expected ${this.address}`);
+    }
+    return signed;
+  }
+
+  async signMessage(message: string | Uint8Array): Promise<string> {
+    const data = typeof message === "string" ? ethers.toUtf8Bytes(message) : message;
+    return await this.signerRpc.send("personal_sign", [ethers.hexlify(data), this.address]);
+  }
+
+  async signTypedData(
+    domain: TypedDataDomain,
+    types: Record<string, TypedDataField[]>,
+    value: Record<string, unknown>
+  ): Promise<string> {
+    const payload = ethers.TypedDataEncoder.getPayload(domain, types, value);
+    return await this.signerRpc.send("eth_signTypedData_v4", [this.address, JSON.stringify(payload)]);
+  }
+
+  // ---- helpers ---------------------------------------------------------------
+
+  connect(provider: ethers.Provider | null): RemoteSigner {
+    return new RemoteSigner(this.address, this.signerUrl, provider);
+  }
+}
@@ -0,0 +1,102 @@
+import "dotenv/config";
+import { Gauge } from "prom-client";
+
+import { BaseFlow } from "./baseFlow";
+import { classifyRpcError } from "./rpcLoggingProvider";
+import { SEC } from "./utils";
+
+import type { JsonRpcProvider } from "ethers";
+
+const FLOW_NAME = "rpc_capability";
+/// methods the flows of the watchdog depend on
+const ETH_METHODS = [
+  "eth_blockNumber",
+  "eth_chainId",
+  "eth_estimateGas",
+  "eth_sendRawTransaction",
+  "eth_getTransactionReceipt",
+  "eth_getLogs",
+  "eth_feeHistory",
+];
+const ZKS_METHODS = ["zks_estimateFee", "zks_estimateGasL1ToL2", "zks_getMainContract", "zks_getL1BatchDetails"];
+const CAPABILITY_METHODS = (process.env.FLOW_RPC_CAPABILITY_METHODS ?? "")
+  .split(",")
+  .map((method) => method.trim())
+  .filter((method) => method.length > 0);
+
+/**
+ * Periodically checks which RPC methods the node serves, so that a node upgrade removing a method the watchdog
+ * depends on (e.g. `zks_estimateGasL1ToL2` for deposits) is detected before it silently breaks a flow. Methods are
+ * called without parameters: any response other than "method not found" (e.g. invalid params) means it's served.
+ */
+export class RpcCapabilityFlow extends BaseFlow {
+  private metricCapability: Gauge;
+  private methods: string[];
+  private available = new Map<string, boolean>();
+
+  constructor(
+    private provider: JsonRpcProvider,
+    isZKsyncOS: boolean,
+    private intervalMs: number
+  ) {
+    super(FLOW_NAME);
+    this.metricCapability = new Gauge({
+      name: "watchdog_rpc_capability",
+      help: "1 if the RPC endpoint serves the method, 0 if it reports it as not found",
+      labelNames: ["method"],
+    });
+    this.methods =
+      CAPABILITY_METHODS.length > 0 ? CAPABILITY_METHODS : isZKsyncOS ? ETH_METHODS : [...ETH_METHODS, ...ZKS_METHODS];
+  }
+
+  /// sent as a raw request, the expected errors would otherwise be logged and counted as failed RPC requests
+  private async isServed(method: string): Promise<boolean> {
+    const request = this.provider._getConnection();
+    request.setHeader("content-type", "application/json");
+    request.body = JSON.stringify({ jsonrpc: "2.0", id: 1, method, params: [] });
+    const response = await request.send();
+    const body = response.bodyJson;
+    return body?.error == null || classifyRpcError({ error: body.error }) !== "method_not_found";
+  }
+
+  public async run() {
+    while (true) {
+      const waitNextExecution = this.startCycle(this.intervalMs);
+
+      try {
+        this.metricRecorder.recordFlowStart();
+
+        const missing = await this.metricRecorder.stepExecution({
+          stepName: "capability",
+          stepTimeoutMs: 30 * SEC,
+          fn: async () => {
+            const missing = [];
+            for (const method of this.methods) {
+              const served = await this.isServed(method);
+              if (!served && this.available.get(method) === true) {
+                this.logger.error(`RPC method ${method} is no longer served`);
+              } else if (served && this.available.get(method) === false) {
+                this.logger.info(`RPC method ${method} is served again`);
+              }
+              this.available.set(method, served);
+              this.metricCapability.set({ method }, served ? 1 : 0);
+              if (!served) missing.push(method);
+            }
+            return missing;
+          },
+        });
+        if (missing.length > 0) {
+          throw new Error(`RPC methods not served: ${missing.join(", ")}`);
+        }
+
+        this.metricRecorder.recordFlowSuccess();
+        // eslint-disable-next-line @typescript-eslint/no-explicit-any
+      } catch (error: any) {
+        this.logger.error("RPC capability error: " + error?.message, error?.stack);
+        this.metricRecorder.recordFlowFailure();
+      }
+
+      await waitNextExecution();
+    }
+  }
+}
@@ -1,9 +1,18 @@
 import { JsonRpcProvider as EthersJsonRpcProvider } from "ethers";
+import { Counter, Gauge } from "prom-client";
 import winston from "winston";
 import { Provider as ZkSyncProvider } from "zksync-ethers";
 import { IBridgehub__factory } from "zksync-ethers/build/typechain";
 
-import type { Networkish, Provider as EthersProvider, TransactionReceipt, JsonRpcApiProviderOptions } from "ethers";
+import { enterRateLimitCooldown } from "./rateLimitCooldown";
+
+import type {
+  FetchRequest,
+  Networkish,
+  Provider as EthersProvider,
+  TransactionReceipt,
+  JsonRpcApiProviderOptions,
+} from "ethers";
 import type { Fee, TransactionRequest } from "zksync-ethers/build/types";
 
 const npmLevels = winston.config.npm.levels;
@@ -12,6 +21,99 @@ const levelEnabled = (level: string): boolean => npmLevels[level] <= npmLevels[w
 
 const bigintReplacer = (_: string, value: unknown): unknown => (typeof value === "bigint" ? value.toString() : value);
 
+const metricRpcFailover = new Counter({
+  name: "watchdog_rpc_failover",
+  help: "Number of RPC endpoint failovers, labeled by the newly active endpoint index",
+  labelNames: ["endpoint_index"],
+});
+
+const metricChainIdChanged = new Gauge({
+  name: "watchdog_chain_id_changed",
+  help: "1 if an RPC endpoint started reporting a different chain id than at startup (sending is then refused)",
+});
+
+const metricRpcError = new Counter({
+  name: "watchdog_rpc_error",
+  help: "Number of failed RPC requests by error category",
+  labelNames: ["category"],
+});
+
+/// methods broadcasting signed transactions, guarded against chain id changes
+const SEND_RAW_TRANSACTION_METHODS = new Set(["eth_sendRawTransaction", "zks_sendRawTransactionWithDetailedOutput"]);
+
+/** Error codes (ethers and node networking) that indicate the endpoint itself is unreachable or broken. */
+const TRANSPORT_ERROR_CODES = new Set([
+  "SERVER_ERROR",
+  "TIMEOUT",
+  "NETWORK_ERROR",
+  "ECONNREFUSED",
+  "ECONNRESET",
+  "ETIMEDOUT",
+  "ENOTFOUND",
+  "EAI_AGAIN",
+  "UND_ERR_CONNECT_TIMEOUT",
+  "UND_ERR_SOCKET",
+]);
+
+// eslint-disable-next-line @typescript-eslint/no-explicit-any
+export function isTransportError(error: any): boolean {
+  if (TRANSPORT_ERROR_CODES.has(error?.code) || TRANSPORT_ERROR_CODES.has(error?.cause?.code)) {
+    return true;
+  }
+  // native fetch (used for authorized requests) rejects with a bare TypeError on connection failures
+  return error instanceof TypeError && error.message === "fetch failed";
+}
+
+export type RpcErrorCategory =
+  | "transport"
+  | "rate_limited"
+  | "payload_too_large"
+  | "nonce"
+  | "insufficient_funds"
+  | "reverted"
+  | "method_not_found"
+  | "other";
+
+/** Sorts an RPC error into a category that tells what kind of problem the endpoint (or the request) has. */
+// eslint-disable-next-line @typescript-eslint/no-explicit-any
+export function classifyRpcError(error: any): RpcErrorCategory {
+  const rpcCode = error?.error?.code ?? error?.info?.error?.code;
+  const message = [error?.message, error?.error?.message, error?.info?.error?.message].join(" ");
+  // checked before transport errors, as ethers reports HTTP 429 as SERVER_ERROR
+  const httpStatus = String(error?.info?.responseStatus ?? "");
+  if (rpcCode === -32005 || httpStatus.startsWith("429") || /rate limit|too many requests/i.test(message)) {
+    return "rate_limited";
+  }
+  // same for HTTP 413, it's the request that is at fault and not the endpoint
+  if (httpStatus.startsWith("413") || /(payload|entity|request|batch|body) (size )?(is )?too large/i.test(message)) {
+    return "payload_too_large";
+  }
+  if (isTransportError(error) || error?.name === "TimeoutError") {
+    return "transport";
+  }
+  if (rpcCode === -32601 || /method not found|does not exist/i.test(message)) {
+    return "method_not_found";
+  }
+  if (error?.code === "NONCE_EXPIRED" || error?.code === "REPLACEMENT_UNDERPRICED" || /nonce/i.test(message)) {
+    return "nonce";
+  }
+  if (error?.code === "INSUFFICIENT_FUNDS" || /insufficient (funds|balance)/i.test(message)) {
+    return "insufficient_funds";
+  }
+  if (error?.code === "CALL_EXCEPTION" || /revert/i.test(message)) {
+    return "reverted";
+  }
+  return "other";
+}
+
+/** Splits a comma-separated list of RPC URLs (e.g. `CHAIN_RPC_URL=http://a,http://b`). */
+function parseRpcUrls(urls: string): string[] {
+  return urls
+    .split(",")
+    .map((url) => url.trim())
+    .filter((url) => url.length > 0);
+}
+
 /** Optional auth token getter for Prividium (Authorization: Bearer). */
 export type AuthTokenGetter = () => string | null;
 
@@ -38,6 +140,8 @@ class AuthableEthersJsonRpcProvider extends EthersJsonRpcProvider {
 class ZkSyncOsProvider extends ZkSyncProvider {
   private l1Provider: EthersProvider | null = null;
   private isZKsyncOS = false;
+  /// static for the lifetime of the chain, resolved once
+  private baseTokenAddress: string | null = null;
   protected readonly rpcUrl: string;
   getAuthToken?: AuthTokenGetter;
 
@@ -60,10 +164,13 @@ class ZkSyncOsProvider extends ZkSyncProvider {
 
   /// method overriden to use L1 calls instead of zks_ method for compatibility with ZKsync OS
   override async getBaseTokenContractAddress(): Promise<string> {
-    const bridgehubAddress = await this.getBridgehubContractAddress();
-    const bridgehub = IBridgehub__factory.connect(bridgehubAddress, this.l1Provider);
-    const chainId = (await this.getNetwork()).chainId;
-    return await bridgehub.baseToken(chainId);
+    if (this.baseTokenAddress == null) {
+      const bridgehubAddress = await this.getBridgehubContractAddress();
+      const bridgehub = IBridgehub__factory.connect(bridgehubAddress, this.l1Provider);
+      const chainId = (await this.getNetwork()).chainId;
+      this.baseTokenAddress = await bridgehub.baseToken(chainId);
+    }
+    return this.baseTokenAddress;
   }
 
   override async estimateFee(transaction: TransactionRequest): Promise<Fee> {
@@ -96,8 +203,88 @@ type Ctor<T = object> = new (...args: any[]) => T;
 const LoggingProviderMixing = <TBase extends Ctor<EthersJsonRpcProvider>>(Base: TBase) => {
   return class LoggingProvider extends Base {
     private requestId: number = 1;
+    private rpcUrls: string[] = [];
+    private activeRpcUrlIndex: number = 0;
+    private chainIdChanged: boolean = false;
+
+    /**
+     * The first argument may be a comma-separated list of RPC URLs. The provider starts with the
+     * first one and fails over to the next on transport errors.
+     */
+    // eslint-disable-next-line @typescript-eslint/no-explicit-any
+    constructor(...args: any[]) {
+      const [url, ...rest] = args;
+      const rpcUrls = typeof url === "string" ? parseRpcUrls(url) : [];
+      super(rpcUrls[0] ?? url, ...rest);
+      this.rpcUrls = rpcUrls;
+    }
+
+    private getActiveRpcUrl(): string | undefined {
+      return this.rpcUrls?.[this.activeRpcUrlIndex];
+    }
+
+    override _getConnection(): FetchRequest {
+      const connection = super._getConnection();
+      // may be called from the base constructor before our fields are initialized
+      if (this.rpcUrls != null && this.rpcUrls.length > 1) {
+        connection.url = this.rpcUrls[this.activeRpcUrlIndex];
+      }
+      return connection;
+    }
+
+    /**
+     * Transactions are signed for the chain id detected at startup (the network is static). If the
+     * endpoint got repointed to another chain, broadcasting them would be wrong at best and replayable at
+     * worst, so once a change is detected we refuse to send anything until restarted. Called once per cycle by
+     * the sending flows, the broadcasts only check the result. A failed check keeps the previous result.
+     */
+    public async checkChainIdUnchanged() {
+      if (this.chainIdChanged) return;
+      try {
+        const expectedChainId = (await this.getNetwork()).chainId;
+        const actualChainId = BigInt((await this.sendWithFailover("eth_chainId", [])) as string);
+        if (actualChainId !== expectedChainId) {
+          this.chainIdChanged = true;
+          metricChainIdChanged.set(1);
+          winston.error(
+            `[JSON-RPC] Chain id changed from ${expectedChainId} to ${actualChainId}, refusing to send transactions until restart`
+          );
+        }
+        // eslint-disable-next-line @typescript-eslint/no-explicit-any
+      } catch (error: any) {
+        winston.warn(`[JSON-RPC] Failed to check the chain id: ${error?.message}`);
+      }
+    }
 
     override async send(method: string, params: unknown[] | Record<string, unknown>): Promise<unknown> {
+      if (SEND_RAW_TRANSACTION_METHODS.has(method) && this.chainIdChanged) {
+        throw new Error("Chain id changed since startup, refusing to send transactions until restart");
+      }
+      return this.sendWithFailover(method, params);
+    }
+
+    private async sendWithFailover(method: string, params: unknown[] | Record<string, unknown>): Promise<unknown> {
+      for (let attempt = 1; ; attempt++) {
+        const usedRpcUrlIndex = this.activeRpcUrlIndex;
+        try {
+          return await this.sendWithLogging(method, params);
+        } catch (error) {
+          if (attempt >= this.rpcUrls.length || !isTransportError(error)) {
+            throw error;
+          }
+          // concurrent requests may have already switched the endpoint, don't skip over the next one
+          if (usedRpcUrlIndex === this.activeRpcUrlIndex) {
+            this.activeRpcUrlIndex = (this.activeRpcUrlIndex + 1) % this.rpcUrls.length;
+            metricRpcFailover.inc({ endpoint_index: this.activeRpcUrlIndex });
+            winston.warn(
+              `[JSON-RPC Failover] Method: ${method} switching to RPC endpoint #${this.activeRpcUrlIndex} after transport error`
+            );
+          }
+        }
+      }
+    }
+
+    private async sendWithLogging(method: string, params: unknown[] | Record<string, unknown>): Promise<unknown> {
       const id = this.requestId++;
       const self = this as typeof this & { getAuthToken?: AuthTokenGetter };
 
@@ -114,7 +301,7 @@ const LoggingProviderMixing = <TBase extends Ctor<EthersJsonRpcProvider>>(Base:
         let result: unknown;
         const token = self.getAuthToken?.();
 
-        const url = getRpcUrl(self);
+        const url = this.getActiveRpcUrl() ?? getRpcUrl(self);
 
         if (token && url) {
           result = await sendAuthorizedRpcRequest(url, token, id, method, params);
@@ -141,6 +328,11 @@ const LoggingProviderMixing = <TBase extends Ctor<EthersJsonRpcProvider>>(Base:
         // eslint-disable-next-line @typescript-eslint/no-explicit-any
       } catch (error: any) {
         const duration = Date.now() - startTime;
+        const category = classifyRpcError(error);
+        metricRpcError.inc({ category });
+        if (category === "rate_limited") {
+          enterRateLimitCooldown(error);
+        }
 
         winston.error(`[JSON-RPC Error] ID: ${id} Method: ${method} Duration: ${duration}ms Error: ${error.message}`, {
           rpcError: {
@@ -270,3 +462,10 @@ async function sendAuthorizedRpcRequest(
 
 export const LoggingZkSyncProvider = LoggingProviderMixing(ZkSyncOsProvider);
 export const LoggingEthersJsonRpcProvider = LoggingProviderMixing(AuthableEthersJsonRpcProvider);
+
+/** Re-checks the chain id of the provider a flow sends with, once per cycle. No-op for non-logging providers. */
+export async function checkChainIdUnchanged(provider: unknown) {
+  if (provider instanceof LoggingZkSyncProvider || provider instanceof LoggingEthersJsonRpcProvider) {
+    await provider.checkChainIdUnchanged();
+  }
+}
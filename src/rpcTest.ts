@@ -1,7 +1,7 @@
 import "dotenv/config";
 
 import { BaseFlow } from "./baseFlow";
-import { SEC, timeoutPromise } from "./utils";
+import { SEC } from "./utils";
 
 import type { Provider } from "zksync-ethers";
 
@@ -17,7 +17,7 @@ export class RpcTestFlow extends BaseFlow {
 
   public async run() {
     while (true) {
-      const nextExecutionWait = timeoutPromise(this.intervalMs);
+      const waitNextExecution = this.startCycle(this.intervalMs);
 
       try {
         this.metricRecorder.recordFlowStart();
@@ -37,7 +37,7 @@ export class RpcTestFlow extends BaseFlow {
         this.metricRecorder.recordFlowFailure();
       }
 
-      await nextExecutionWait;
+      await waitNextExecution();
     }
   }
 }
@@ -0,0 +1,117 @@
+import "dotenv/config";
+import { TypedDataEncoder, ZeroAddress } from "ethers";
+
+import { BaseFlow } from "./baseFlow";
+import { SEC } from "./utils";
+
+import type { Signer } from "ethers";
+
+const FLOW_NAME = "safe_proposal";
+
+const SAFE_TX_TYPES = {
+  SafeTx: [
+    { type: "address", name: "to" },
+    { type: "uint256", name: "value" },
+    { type: "bytes", name: "data" },
+    { type: "uint8", name: "operation" },
+    { type: "uint256", name: "safeTxGas" },
+    { type: "uint256", name: "baseGas" },
+    { type: "uint256", name: "gasPrice" },
+    { type: "address", name: "gasToken" },
+    { type: "address", name: "refundReceiver" },
+    { type: "uint256", name: "nonce" },
+  ],
+};
+
+/**
+ * Monitors wallets held in a Gnosis Safe: instead of broadcasting a transaction, it proposes a no-op
+ * transaction (0 value call to the Safe itself) to the Safe Transaction Service and measures the
+ * proposal latency. The watchdog wallet has to be an owner (or delegate) of the Safe.
+ */
+export class SafeProposalFlow extends BaseFlow {
+  constructor(
+    private signer: Signer,
+    private chainId: bigint,
+    private txServiceUrl: string,
+    private safeAddress: string,
+    private intervalMs: number
+  ) {
+    super(FLOW_NAME);
+  }
+
+  private async fetchJson(url: string, init: RequestInit, signal: AbortSignal): Promise<unknown> {
+    const res = await fetch(url, { ...init, signal });
+    if (!res.ok) {
+      throw new Error(`Safe Transaction Service request ${url} failed: ${res.status} ${await res.text()}`);
+    }
+    return res.status === 204 ? null : res.json();
+  }
+
+  protected async step() {
+    try {
+      this.metricRecorder.recordFlowStart();
+      const safeUrl = `${this.txServiceUrl}/api/v1/safes/${this.safeAddress}`;
+
+      const nonce = await this.metricRecorder.stepExecution({
+        stepName: "get_nonce",
+        stepTimeoutMs: 10 * SEC,
+        fn: async ({ signal }) => {
+          const safeInfo = (await this.fetchJson(`${safeUrl}/`, {}, signal)) as { nonce: number | string };
+          return safeInfo.nonce;
+        },
+      });
+
+      await this.metricRecorder.stepExecution({
+        stepName: "propose",
+        stepTimeoutMs: 10 * SEC,
+        fn: async ({ signal }) => {
+          const safeTx = {
+            to: this.safeAddress,
+            value: "0",
+            data: "0x",
+            operation: 0,
+            safeTxGas: "0",
+            baseGas: "0",
+            gasPrice: "0",
+            gasToken: ZeroAddress,
+            refundReceiver: ZeroAddress,
+            nonce: nonce.toString(),
+          };
+          const domain = { chainId: this.chainId, verifyingContract: this.safeAddress };
+          const safeTxHash = TypedDataEncoder.hash(domain, SAFE_TX_TYPES, safeTx);
+          const signature = await this.signer.signTypedData(domain, SAFE_TX_TYPES, safeTx);
+          await this.fetchJson(
+            `${safeUrl}/multisig-transactions/`,
+            {
+              method: "POST",
+              headers: { "Content-Type": "application/json" },
+              body: JSON.stringify({
+                ...safeTx,
+                contractTransactionHash: safeTxHash,
+                sender: await this.signer.getAddress(),
+                signature,
+                origin: "era-watchdog",
+              }),
+            },
+            signal
+          );
+          this.logger.info(`Proposed Safe transaction ${safeTxHash} with nonce ${nonce}`);
+        },
+      });
+
+      this.metricRecorder.recordFlowSuccess();
+      // eslint-disable-next-line @typescript-eslint/no-explicit-any
+    } catch (error: any) {
+      this.logger.error("Safe proposal error: " + error?.message, error?.stack);
+      this.metricRecorder.recordFlowFailure();
+    }
+  }
+
+  public async run() {
+    while (true) {
+      const waitNextExecution = this.startCycle(this.intervalMs);
+      await this.step();
+      await waitNextExecution();
+    }
+  }
+}
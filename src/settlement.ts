@@ -3,7 +3,7 @@ import "dotenv/config";
 import { Gauge } from "prom-client";
 
 import { BaseFlow } from "./baseFlow";
-import { SEC, timeoutPromise } from "./utils";
+import { SEC } from "./utils";
 
 import type { Provider } from "ethers";
 import type { Provider as ZkSyncProvider } from "zksync-ethers";
@@ -28,7 +28,7 @@ export class SettlementFlow extends BaseFlow {
 
   public async run() {
     while (true) {
-      const nextExecutionWait = timeoutPromise(this.intervalMs);
+      const waitNextExecution = this.startCycle(this.intervalMs);
 
       try {
         this.metricRecorder.recordFlowStart();
@@ -92,7 +92,7 @@ export class SettlementFlow extends BaseFlow {
         this.metricRecorder.recordFlowFailure();
       }
 
-      await nextExecutionWait;
+      await waitNextExecution();
     }
   }
 }
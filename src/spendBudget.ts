@@ -0,0 +1,50 @@
+import { Gauge } from "prom-client";
+import winston from "winston";
+
+import { MIN } from "./utils";
+
+/// cap on the fees paid by all flows together within the rolling window, unset for no cap
+const MAX_TOTAL_SPEND_WEI = process.env.MAX_TOTAL_SPEND_WEI ? BigInt(process.env.MAX_TOTAL_SPEND_WEI) : null;
+const MAX_TOTAL_SPEND_WINDOW = +(process.env.MAX_TOTAL_SPEND_WINDOW ?? 24 * 60 * MIN);
+
+const metricBudgetExhausted = new Gauge({
+  name: "watchdog_budget_exhausted",
+  help: "1 while the fees paid within the spend window exceed MAX_TOTAL_SPEND_WEI (flows stop sending), 0 otherwise",
+});
+metricBudgetExhausted.set(0);
+
+/// fees paid by mined transactions, oldest first
+const spends: { timestamp: number; cost: bigint }[] = [];
+let exhausted = false;
+
+function spentInWindow(): bigint {
+  const windowStart = Date.now() - MAX_TOTAL_SPEND_WINDOW;
+  while (spends.length > 0 && spends[0].timestamp < windowStart) {
+    spends.shift();
+  }
+  return spends.reduce((total, spend) => total + spend.cost, 0n);
+}
+
+/** Adds the fees paid by a mined transaction to the spend window. */
+export function recordSpend(cost: bigint) {
+  if (MAX_TOTAL_SPEND_WEI == null) return;
+  spends.push({ timestamp: Date.now(), cost });
+}
+
+/**
+ * Whether the fees paid within the window exceed `MAX_TOTAL_SPEND_WEI`. Flows that send transactions check it
+ * before sending, so that a bug causing runaway spend is capped, while read-only probes keep running.
+ */
+export function isSpendBudgetExhausted(): boolean {
+  if (MAX_TOTAL_SPEND_WEI == null) return false;
+  const spent = spentInWindow();
+  const wasExhausted = exhausted;
+  exhausted = spent > MAX_TOTAL_SPEND_WEI;
+  metricBudgetExhausted.set(exhausted ? 1 : 0);
+  if (exhausted && !wasExhausted) {
+    winston.error(`Spent ${spent} wei within the spend window, over MAX_TOTAL_SPEND_WEI. Stopped sending transactions`);
+  } else if (wasExhausted && !exhausted) {
+    winston.info(`Spent ${spent} wei within the spend window, back under MAX_TOTAL_SPEND_WEI. Resumed sending`);
+  }
+  return exhausted;
+}
@@ -0,0 +1,57 @@
+import { readFileSync, renameSync, writeFileSync } from "fs";
+
+export type PendingOperation = {
+  type: string;
+  txHash: string;
+  /// in ms
+  startedAt: number;
+};
+
+/**
+ * Small on-disk store of operations that take long to complete (e.g. withdrawals waiting to become
+ * finalizable), so that a restart doesn't lose track of them and their end-to-end latency. Without a file
+ * the store only lives in memory.
+ */
+export class PendingOperationStore {
+  private operations: PendingOperation[];
+
+  constructor(private path: string | undefined) {
+    this.operations = path != null ? this.load(path) : [];
+  }
+
+  private load(path: string): PendingOperation[] {
+    try {
+      return JSON.parse(readFileSync(path, "utf8")) as PendingOperation[];
+      // eslint-disable-next-line @typescript-eslint/no-explicit-any
+    } catch (error: any) {
+      if (error?.code === "ENOENT") {
+        return [];
+      }
+      throw error;
+    }
+  }
+
+  private save() {
+    if (this.path == null) return;
+    // write + rename so that a crash never leaves a partially written file
+    const tmpFile = `${this.path}.tmp`;
+    writeFileSync(tmpFile, JSON.stringify(this.operations));
+    renameSync(tmpFile, this.path);
+  }
+
+  public list(type: string): PendingOperation[] {
+    return this.operations.filter((operation) => operation.type === type);
+  }
+
+  public add(operation: PendingOperation) {
+    this.operations.push(operation);
+    this.save();
+  }
+
+  public remove(txHash: string) {
+    this.operations = this.operations.filter((operation) => operation.txHash !== txHash);
+    this.save();
+  }
+}
+
+export const pendingOperations = new PendingOperationStore(process.env.STATE_FILE);
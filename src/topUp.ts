@@ -0,0 +1,47 @@
+import { Counter } from "prom-client";
+import winston from "winston";
+
+import { MIN, SEC } from "./utils";
+
+/// faucet or top-up endpoint of the wallet, `{address}` is replaced by the wallet address
+const TOPUP_URL = process.env.TOPUP_URL;
+const TOPUP_MIN_BALANCE_WEI = BigInt(process.env.TOPUP_MIN_BALANCE_WEI ?? 0);
+/// faucets are usually rate limited, and the funds take a while to arrive
+const TOPUP_MIN_INTERVAL = +(process.env.TOPUP_MIN_INTERVAL ?? 60 * MIN);
+const TOPUP_TIMEOUT = 30 * SEC;
+
+const metricTopUpRequested = new Counter({
+  name: "watchdog_wallet_topup_requested",
+  help: "Number of top-up requests sent to TOPUP_URL because the L2 balance dropped below TOPUP_MIN_BALANCE_WEI",
+  labelNames: ["outcome"],
+});
+
+/// last request in ms
+let lastRequestMs = 0;
+
+/**
+ * Requests funds from `TOPUP_URL` (a `POST` with the address as JSON) when the L2 balance of the wallet dropped
+ * below `TOPUP_MIN_BALANCE_WEI`, so that unattended testnet watchdogs stay funded. Best-effort, at most once per
+ * `TOPUP_MIN_INTERVAL`.
+ */
+export function requestTopUpIfLow(address: string, balance: bigint) {
+  if (TOPUP_URL == null || balance >= TOPUP_MIN_BALANCE_WEI) return;
+  const now = Date.now();
+  if (now - lastRequestMs < TOPUP_MIN_INTERVAL) return;
+  lastRequestMs = now;
+  winston.info(`L2 balance ${balance} wei of ${address} is below TOPUP_MIN_BALANCE_WEI, requesting a top-up`);
+  fetch(TOPUP_URL.replace("{address}", address), {
+    method: "POST",
+    signal: AbortSignal.timeout(TOPUP_TIMEOUT),
+    headers: { "Content-Type": "application/json" },
+    body: JSON.stringify({ address }),
+  })
+    .then((response) => {
+      metricTopUpRequested.inc({ outcome: response.ok ? "success" : "failure" });
+      if (!response.ok) winston.warn(`Top-up request failed with HTTP ${response.status}`);
+    })
+    .catch((error) => {
+      metricTopUpRequested.inc({ outcome: "failure" });
+      winston.warn(`Top-up request failed: ${error?.message}`);
+    });
+}
@@ -0,0 +1,55 @@
+import { appendFile, stat } from "fs/promises";
+import winston from "winston";
+
+/// path of the CSV file each completed cycle of the flows is appended to
+const TRACE_CSV_PATH = process.env.TRACE_CSV_PATH;
+const TRACE_CSV_HEADER = "timestamp,flow,status,latency_total,step_latencies,step_gas,cost_wei\n";
+
+export type CycleTrace = {
+  /// seconds per step, in execution order
+  latencies: Record<string, number>;
+  gas: Record<string, number>;
+  costWei: bigint;
+};
+
+/// writes are chained, so that the rows keep the completion order of the cycles
+let pendingWrite: Promise<void> = Promise.resolve();
+
+/** Steps as `step=value` pairs separated by `;`, so that the number of columns doesn't depend on the flow. */
+const formatSteps = (values: Record<string, number>): string =>
+  Object.entries(values)
+    .map(([step, value]) => `${step}=${value}`)
+    .join(";");
+
+const writeRow = async (path: string, row: string) => {
+  let size = 0;
+  try {
+    size = (await stat(path)).size;
+    // eslint-disable-next-line @typescript-eslint/no-explicit-any
+  } catch (error: any) {
+    if (error?.code !== "ENOENT") throw error;
+  }
+  await appendFile(path, size === 0 ? TRACE_CSV_HEADER + row : row);
+};
+
+/**
+ * Appends the metrics of a completed cycle to `TRACE_CSV_PATH` (if set), a portable record to diff across deploys or
+ * load into a notebook independently of the metrics backend. Failures to write it are logged and don't affect the
+ * flows.
+ */
+export function appendCycleTrace(flow: string, status: string, latencyTotalSec: number | null, trace: CycleTrace) {
+  if (TRACE_CSV_PATH == null) return;
+  const path = TRACE_CSV_PATH;
+  const row = [
+    new Date().toISOString(),
+    flow,
+    status,
+    latencyTotalSec ?? "",
+    formatSteps(trace.latencies),
+    formatSteps(trace.gas),
+    trace.costWei,
+  ].join(",");
+  pendingWrite = pendingWrite
+    .then(() => writeRow(path, row + "\n"))
+    .catch((error) => winston.error(`Failed to write the cycle trace to ${path}: ${error?.message}`));
+}
@@ -1,14 +1,41 @@
 import "dotenv/config";
-import { utils } from "zksync-ethers";
+import { dataLength, hexlify, randomBytes } from "ethers";
+import { Counter, Gauge, Histogram } from "prom-client";
+import { types, utils, Wallet as ZkSyncWallet } from "zksync-ethers";
 
 import { BaseFlow } from "./baseFlow";
-import { L2_EXECUTION_TIMEOUT } from "./configs";
+import { warnIfClockSkewExceeded } from "./clockSkew";
+import {
+  CONFIRMATION_BLOCK_TAG,
+  CONFIRMATION_MODE,
+  ESTIMATE_BLOCK_TAG,
+  ESTIMATE_SEND_DELAY_MS,
+  EXIT_ON_REVERT,
+  FINALIZATION_TIMEOUT,
+  FIXED_GAS_LIMIT,
+  L2_EXECUTION_TIMEOUT,
+  MAX_MEMPOOL_WAIT_SECONDS,
+} from "./configs";
+import { FatalError, isPaymasterError, toWatchdogError, WatchdogError, WatchdogErrorKind } from "./errors";
+import { detectFeeModelVersion } from "./feeModel";
+import {
+  applyPriorityFeeFloor,
+  bumpFees,
+  getFeeOverrides,
+  isDuplicateTransaction,
+  isNonceTooLow,
+  isReplacementUnderpriced,
+} from "./fees";
 import { recordL2BaseTokenBalance, StatusNoSkip } from "./flowMetric";
+import { waitForRateLimitCooldown } from "./rateLimitCooldown";
+import { checkChainIdUnchanged, classifyRpcError, isTransportError } from "./rpcLoggingProvider";
+import { isSpendBudgetExhausted, recordSpend } from "./spendBudget";
+import { requestTopUpIfLow } from "./topUp";
 import { SEC, timeoutPromise, unwrap } from "./utils";
 
 import type { Mutex } from "./lock";
 import type { Provider as EthersProvider, Signer as EthersSigner } from "ethers";
-import type { types, Provider, Wallet as ZkSyncWallet } from "zksync-ethers";
+import type { Provider } from "zksync-ethers";
 
 /** A Signer that also exposes a synchronous `.address` property. */
 type SignerWithAddress = EthersSigner & { readonly address: string };
@@ -16,109 +43,851 @@ type SignerWithAddress = EthersSigner & { readonly address: string };
 const FLOW_NAME = "transfer";
 const TRANSFER_RETRY_LIMIT = +(process.env.FLOW_TRANSFER_RETRY_LIMIT ?? 5);
 const TRANSFER_RETRY_INTERVAL = +(process.env.FLOW_TRANSFER_RETRY_INTERVAL ?? 5 * SEC);
+/// fee increase (in percent, compounding) applied each time the node rejects the tx as replacement underpriced
+const UNDERPRICED_FEE_BUMP_PERCENT = +(process.env.FLOW_TRANSFER_UNDERPRICED_FEE_BUMP_PERCENT ?? 20);
+const UNDERPRICED_BUMP_LIMIT = +(process.env.FLOW_TRANSFER_UNDERPRICED_BUMP_LIMIT ?? 3);
+/// when set, the probe calls this contract instead of transferring to self and expects it to emit an event
+const EVENT_CONTRACT = process.env.FLOW_TRANSFER_EVENT_CONTRACT;
+const EVENT_CALLDATA = process.env.FLOW_TRANSFER_EVENT_CALLDATA ?? "0x";
+/// comma-separated bytecodes attached to the probe as factory deps (ZKsync transactions only), to track DA cost
+const FACTORY_DEPS = (process.env.FLOW_TRANSFER_FACTORY_DEPS ?? "").split(",").filter((dep) => dep.length > 0);
+/// consecutive paymaster-specific failures after which the paymaster is reported as degraded
+const PAYMASTER_FAILURE_THRESHOLD = +(process.env.FLOW_TRANSFER_PAYMASTER_FAILURE_THRESHOLD ?? 3);
+/// when the paymaster is degraded, retry attempts pay for themselves instead of using the paymaster
+const PAYMASTER_FALLBACK = process.env.FLOW_TRANSFER_PAYMASTER_FALLBACK === "1";
+
+/// size of pseudo-random calldata attached to the self-transfer, to monitor inclusion latency of large transactions
+const DATA_SIZE_BYTES = +(process.env.FLOW_TRANSFER_DATA_SIZE_BYTES ?? 0);
+/// per-transaction pubdata limit of the chain, the data size can't exceed it
+const MAX_DATA_SIZE_BYTES = +(process.env.FLOW_TRANSFER_MAX_DATA_SIZE_BYTES ?? 120_000);
+/// when the endpoint rejects the request as too large, halve the data size for the following attempts
+const DATA_SIZE_SHRINK = process.env.FLOW_TRANSFER_DATA_SIZE_SHRINK === "1";
+if (DATA_SIZE_BYTES > MAX_DATA_SIZE_BYTES) {
+  throw new WatchdogError(
+    WatchdogErrorKind.config,
+    `FLOW_TRANSFER_DATA_SIZE_BYTES ${DATA_SIZE_BYTES} exceeds the per-transaction limit of ${MAX_DATA_SIZE_BYTES} bytes`
+  );
+}
+
+/// number of transactions kept in flight at once per attempt (sent with consecutive nonces)
+const CONCURRENCY = +(process.env.FLOW_TRANSFER_CONCURRENCY ?? 1);
+/// with a concurrency of 1, every Nth cycle sends 2 transactions at consecutive nonces for the ordering check (0 never)
+const ORDERING_CHECK_EVERY = +(process.env.FLOW_TRANSFER_ORDERING_CHECK_EVERY ?? 0);
+/// attach the access list generated by `eth_createAccessList` (non-paymaster transactions only)
+const ACCESS_LIST = process.env.FLOW_TRANSFER_ACCESS_LIST === "1";
+/// submit via `zks_sendRawTransactionWithDetailedOutput` to also monitor the events and storage logs of the execution
+const DETAILED_OUTPUT = process.env.FLOW_TRANSFER_DETAILED_OUTPUT === "1";
+/// also estimate with `zks_estimateFee` (ZKsync transactions only) and compare its gas limit to the gas used
+const ESTIMATE_FEE_ACCURACY = process.env.FLOW_TRANSFER_ESTIMATE_FEE_ACCURACY === "1";
+/// send right after the expected seal of a block, the block time being learned from the recent block timestamps
+const ALIGN_TO_BLOCKS = process.env.FLOW_TRANSFER_ALIGN_TO_BLOCKS === "1";
+const ALIGN_SAMPLE_BLOCKS = 10;
+/// after inclusion, also wait until the L1 batch of the transaction is executed on L1 (ZKsync transactions only)
+const L2_FINALITY = process.env.FLOW_TRANSFER_L2_FINALITY === "1";
+
+/// key figures of the last attempt, logged once per cycle (bigints as strings, so they can be serialized)
+type AttemptSummary = {
+  gas_estimate?: string;
+  gas_used?: string;
+  cost?: string;
+  nonce?: number;
+  block_number?: number;
+  error_kind?: WatchdogErrorKind;
+};
 
 export class SimpleTxFlow extends BaseFlow {
+  private metricEventEmitted: Gauge;
+  private metricPaymasterStatus: Gauge;
+  /// consecutive paymaster-specific failures by paymaster address
+  private paymasterFailures = new Map<string, number>();
+  /// paymaster of the current cycle, rotating through the configured ones
+  private paymasterAddress: string | undefined;
+  private cycle = 0;
+  private metricDetailedOutputEvents: Gauge;
+  private metricDetailedOutputStorageLogs: Gauge;
+  private metricReplacedExternally: Counter;
+  private metricExceedsBlockLimit: Gauge;
+  private metricInclusionLatency: Histogram;
+  private metricSendOutcome: Counter;
+  private metricSendFailure: Counter;
+  private metricSendBlockOffset: Gauge;
+  private metricL2FinalityLatency: Gauge;
+  private trackL2Finality = false;
+  private metricFeeHeadroom: Gauge;
+  private metricConsecutiveSuccesses: Gauge;
+  private metricSlaMet: Gauge;
+  private metricEstimateFeeRatio: Gauge;
+  private metricOrderingCorrect: Gauge;
+  private metricReceiptConsistent: Gauge;
+  private metricConfirmationLatency: Gauge;
+  /// falls back to `latest` when the node doesn't support the configured tag
+  private confirmationTag = CONFIRMATION_BLOCK_TAG;
+  private consecutiveSuccesses = 0;
+  /// calldata size of the self-transfer, decreases when shrinking on payload too large rejections
+  private dataSize = DATA_SIZE_BYTES;
+  /// fetched once, a transaction estimated above it can never be mined
+  private blockGasLimit: bigint | null = null;
+  /// only set when factory deps are configured and can be attached
+  private factoryDeps: string[] = [];
+  /// hashes of all our transactions since the last success, so that our own earlier attempts aren't reported as
+  /// replacements
+  private sentTxHashes = new Set<string>();
+  private metricDuplicateRejected: Counter;
+  /// signed transactions of the last failed attempt, broadcast again as is by the next attempt of the cycle
+  private retrySignedTxs: string[] | null = null;
+  private attemptSummary: AttemptSummary = {};
+  private metricAccessListEntries: Gauge;
+  private metricAccessListGasSaved: Gauge;
+  /// cleared when the node turns out not to support `eth_createAccessList`
+  private useAccessList = ACCESS_LIST;
+  /// detected at startup, `null` if unknown
+  private feeModelVersion: number | null = null;
+  /// cleared when the node turns out not to support the detailed output method
+  private useDetailedOutput = DETAILED_OUTPUT;
+
   constructor(
     private provider: Provider,
     private wallet: ZkSyncWallet | SignerWithAddress,
     private l2WalletLock: Mutex,
-    private paymasterAddress: string | undefined,
+    private paymasterAddresses: string[],
     private intervalMs: number,
-    private l2EthersProvider: EthersProvider | null = null
+    private l2EthersProvider: EthersProvider | null = null,
+    /// pushes new heads, so that inclusion is detected as soon as the block is produced instead of at the next poll
+    private wsProvider: EthersProvider | null = null
   ) {
     super(FLOW_NAME);
+    this.metricEventEmitted = new Gauge({
+      name: "watchdog_tx_event_emitted",
+      help: "1 if the receipt of the last transfer contained the event of the configured contract, 0 otherwise",
+    });
+    this.metricPaymasterStatus = new Gauge({
+      name: "watchdog_paymaster_status",
+      help: "1 if the paymaster is healthy, 0 if it failed repeatedly with paymaster-specific errors",
+      labelNames: ["paymaster"],
+    });
+    for (const paymaster of this.paymasterAddresses) {
+      this.metricPaymasterStatus.set({ paymaster }, 1);
+    }
+    this.metricDetailedOutputEvents = new Gauge({
+      name: "watchdog_tx_detailed_output_events",
+      help: "Number of events reported by zks_sendRawTransactionWithDetailedOutput for the last transfer",
+    });
+    this.metricDetailedOutputStorageLogs = new Gauge({
+      name: "watchdog_tx_detailed_output_storage_logs",
+      help: "Number of storage logs reported by zks_sendRawTransactionWithDetailedOutput for the last transfer",
+    });
+    this.metricReplacedExternally = new Counter({
+      name: "watchdog_tx_replaced_externally",
+      help: "Number of transfers whose nonce was used by a transaction not sent by the watchdog (shared wallet)",
+    });
+    this.metricDuplicateRejected = new Counter({
+      name: "watchdog_tx_duplicate_rejected",
+      help: "Number of retried transfers rejected as duplicates, i.e. the transaction of the failed attempt had landed",
+    });
+    this.metricAccessListEntries = new Gauge({
+      name: "watchdog_tx_access_list_entries",
+      help: "Number of entries in the access list generated by eth_createAccessList for the last transfer",
+    });
+    this.metricAccessListGasSaved = new Gauge({
+      name: "watchdog_tx_access_list_gas_saved",
+      help: "Gas estimate of the last transfer without its access list minus the gas used with it",
+    });
+    this.metricExceedsBlockLimit = new Gauge({
+      name: "watchdog_tx_exceeds_block_limit",
+      help: "1 if the gas estimate of the last transfer exceeded the block gas limit (so it wasn't sent), 0 otherwise",
+    });
+    this.metricSendOutcome = new Counter({
+      name: "watchdog_tx_send_outcome",
+      help: "Number of transfer sends by `outcome` (success, failure) and `delay_ms` between estimation and send",
+      labelNames: ["delay_ms", "outcome"],
+    });
+    this.metricSendFailure = new Counter({
+      name: "watchdog_tx_send_failure",
+      help: "Number of failed transfer sends by `origin`: `client` (signing, nonce, fees) or `node` (rejected by it)",
+      labelNames: ["origin"],
+    });
+    this.metricSendBlockOffset = new Gauge({
+      name: "watchdog_tx_send_block_offset",
+      help: "Seconds between the expected seal of a block and the send of the transfer right after it",
+    });
+    this.metricL2FinalityLatency = new Gauge({
+      name: "watchdog_tx_l2_finality_latency",
+      help: "Seconds from sending the last transfer until its L1 batch was executed on L1 (FLOW_TRANSFER_L2_FINALITY)",
+    });
+    this.metricInclusionLatency = new Histogram({
+      name: "watchdog_tx_inclusion_latency",
+      help: "Seconds from sending the transfer until its inclusion in a block, by `data_size` of its calldata in bytes",
+      labelNames: ["data_size"],
+      buckets: [0.25, 0.5, 1, 2, 5, 10, 30, 60],
+    });
+    this.metricFeeHeadroom = new Gauge({
+      name: "watchdog_tx_fee_headroom",
+      help: "Max fee per gas of the last transfer divided by the base fee of the block it was included in",
+    });
+    this.metricConsecutiveSuccesses = new Gauge({
+      name: "watchdog_tx_consecutive_successes",
+      help: "Number of consecutive successful transfer cycles, reset to 0 by a failed cycle",
+    });
+    this.metricConsecutiveSuccesses.set(0);
+    this.metricConfirmationLatency = new Gauge({
+      name: "watchdog_tx_confirmation_latency",
+      help: "Seconds from the inclusion of the last transfer until its block reached the `tag` block in safe mode",
+      labelNames: ["tag"],
+    });
+    this.metricReceiptConsistent = new Gauge({
+      name: "watchdog_tx_receipt_consistent",
+      help: "1 if the receipts of the last transfer matched the transactions sent (hash, from, to, gas), 0 otherwise",
+    });
+    this.metricOrderingCorrect = new Gauge({
+      name: "watchdog_tx_ordering_correct",
+      help: "1 if the consecutive-nonce transfers of the last ordering check were mined in nonce order, 0 otherwise",
+    });
+    this.metricEstimateFeeRatio = new Gauge({
+      name: "watchdog_tx_estimate_fee_ratio",
+      help: "Gas limit estimated by zks_estimateFee for the last transfer divided by the gas it used",
+    });
+    this.metricSlaMet = new Gauge({
+      name: "watchdog_tx_sla_met",
+      help: "1 if the last transfer was included within MAX_MEMPOOL_WAIT_SECONDS, 0 if it wasn't",
+    });
+    if (FACTORY_DEPS.length > 0) {
+      if (this.wallet instanceof ZkSyncWallet) {
+        this.factoryDeps = FACTORY_DEPS;
+        // the payload is static, so the metrics are only set once
+        new Gauge({
+          name: "watchdog_tx_factory_deps_count",
+          help: "Number of factory deps attached to the transfer",
+        }).set(FACTORY_DEPS.length);
+        new Gauge({
+          name: "watchdog_tx_estimated_pubdata_bytes",
+          help: "Estimated pubdata of the transfer in bytes (published bytecodes of its factory deps)",
+        }).set(FACTORY_DEPS.reduce((total, dep) => total + dataLength(dep), 0));
+      } else {
+        this.logger.warn("FLOW_TRANSFER_FACTORY_DEPS is only supported for ZKsync transactions, ignoring it");
+      }
+    }
+    if (L2_FINALITY) {
+      if (this.wallet instanceof ZkSyncWallet) {
+        this.trackL2Finality = true;
+      } else {
+        this.logger.warn("FLOW_TRANSFER_L2_FINALITY is only supported for ZKsync transactions, ignoring it");
+      }
+    }
   }
 
-  protected getTxRequest(): types.TransactionRequest {
-    if (this.paymasterAddress != null) {
+  private async sendWithDetailedOutput(signedTx: string): Promise<{ hash: string }> {
+    try {
+      const output = await this.provider.send("zks_sendRawTransactionWithDetailedOutput", [signedTx]);
+      this.metricDetailedOutputEvents.set(output.events?.length ?? 0);
+      this.metricDetailedOutputStorageLogs.set(output.storageLogs?.length ?? 0);
+      return { hash: output.transactionHash };
+    } catch (error) {
+      if (classifyRpcError(error) !== "method_not_found") {
+        throw error;
+      }
+      this.logger.warn("zks_sendRawTransactionWithDetailedOutput is not supported, falling back to standard send");
+      this.useDetailedOutput = false;
+      return await this.provider.broadcastTransaction(signedTx);
+    }
+  }
+
+  private isPaymasterDegraded(): boolean {
+    return (this.paymasterFailures.get(unwrap(this.paymasterAddress)) ?? 0) >= PAYMASTER_FAILURE_THRESHOLD;
+  }
+
+  private recordPaymasterResult(success: boolean) {
+    const paymaster = unwrap(this.paymasterAddress);
+    const wasDegraded = this.isPaymasterDegraded();
+    const failures = success ? 0 : (this.paymasterFailures.get(paymaster) ?? 0) + 1;
+    this.paymasterFailures.set(paymaster, failures);
+    this.metricPaymasterStatus.set({ paymaster }, this.isPaymasterDegraded() ? 0 : 1);
+    if (this.isPaymasterDegraded() && !wasDegraded) {
+      this.logger.error(
+        `Paymaster ${paymaster} failed ${failures} times in a row` +
+          (PAYMASTER_FALLBACK ? ", falling back to self-paying transactions on retries" : "")
+      );
+    } else if (wasDegraded && !this.isPaymasterDegraded()) {
+      this.logger.info(`Paymaster ${paymaster} recovered`);
+    }
+  }
+
+  protected getTxRequest(usePaymaster: boolean): types.TransactionRequest {
+    const target =
+      EVENT_CONTRACT != null
+        ? { to: EVENT_CONTRACT, data: EVENT_CALLDATA }
+        : { to: this.wallet.address, ...(this.dataSize > 0 && { data: hexlify(randomBytes(this.dataSize)) }) };
+    if (usePaymaster && this.paymasterAddress != null) {
       const paymasterParams = utils.getPaymasterParams(this.paymasterAddress, {
         type: "General",
         innerInput: new Uint8Array(),
       });
       return {
-        to: this.wallet.address,
+        ...target,
         value: 0, // in paymaster scenario we may not have any funds
         customData: {
           gasPerPubdata: utils.DEFAULT_GAS_PER_PUBDATA_LIMIT,
           paymasterParams,
+          ...(this.factoryDeps.length > 0 && { factoryDeps: this.factoryDeps }),
         },
       };
     } else {
       return {
-        to: this.wallet.address,
-        value: 1, // just 1 wei
+        ...target,
+        value: EVENT_CONTRACT != null ? 0 : 1, // just 1 wei, the event contract doesn't need to be payable
+        ...(this.factoryDeps.length > 0 && {
+          customData: { gasPerPubdata: utils.DEFAULT_GAS_PER_PUBDATA_LIMIT, factoryDeps: this.factoryDeps },
+        }),
       };
     }
   }
 
-  protected async step(): Promise<StatusNoSkip> {
+  protected async step(usePaymaster: boolean): Promise<StatusNoSkip> {
+    const sentTxs: { hash: string; nonce: number; sentAtSec: number }[] = [];
+    // by index, kept for the next attempt if this one fails
+    const signedTxs: string[] = [];
+    let resendRejected = false;
+    // once a receipt was obtained, rebroadcasting the transactions makes no sense
+    let included = false;
+    let estimateFeeGasLimit: bigint | null = null;
+    this.attemptSummary = {};
+    // kind of the failures of the current stage
+    let stage: WatchdogErrorKind = WatchdogErrorKind.estimation;
+    // whether a failure of the send stage happened locally or on the node, as it changes who gets paged
+    let sendOrigin: "client" | "node" = "client";
     try {
       this.metricRecorder.recordFlowStart();
 
       // populate transaction
-      const tx = this.getTxRequest();
-      const populated = await this.metricRecorder.stepExecution({
+      let tx = this.getTxRequest(usePaymaster);
+      const populated = await this.metricRecorder.estimationStepExecution({
         stepName: "estimation",
         stepTimeoutMs: 10 * SEC,
         fn: async ({ recordStepGas, recordStepGasPrice, recordStepGasCost }) => {
           const latestNonce = await this.wallet.getNonce("latest");
-          const populated = await this.wallet.populateTransaction({
+          if (this.useAccessList && tx.customData == null) {
+            tx = await this.withAccessList(tx);
+          }
+          if (tx.customData != null && this.feeModelVersion === 2) {
+            // the default gas per pubdata limit only fits the fixed pubdata price of the V1 fee model
+            const fee = await this.provider.estimateFee({ ...tx, from: this.wallet.address });
+            tx = { ...tx, customData: { ...tx.customData, gasPerPubdata: fee.gasPerPubdataLimit } };
+            estimateFeeGasLimit = fee.gasLimit;
+          }
+          if (ESTIMATE_FEE_ACCURACY && this.wallet instanceof ZkSyncWallet && estimateFeeGasLimit == null) {
+            estimateFeeGasLimit = (await this.provider.estimateFee({ ...tx, from: this.wallet.address })).gasLimit;
+          }
+          // a set gas limit is kept by populateTransaction, which otherwise estimates without a block tag
+          const gasLimit =
+            FIXED_GAS_LIMIT ??
+            (ESTIMATE_BLOCK_TAG != null ? await this.estimateGasAt(tx, ESTIMATE_BLOCK_TAG) : undefined);
+          let populated = await this.wallet.populateTransaction({
             ...tx,
+            ...getFeeOverrides(),
+            ...(gasLimit != null && { gasLimit }),
             nonce: latestNonce,
           });
+          const floored = applyPriorityFeeFloor(populated);
+          if (floored != null) {
+            this.logger.debug(`Estimated priority fee ${populated.maxPriorityFeePerGas} raised to the floor`);
+            this.metricRecorder.recordPriorityFeeFloorApplied();
+            populated = floored;
+          }
           const gasPrice = unwrap(populated.maxFeePerGas || populated.gasPrice);
           recordStepGasPrice(gasPrice);
           recordStepGas(unwrap(populated.gasLimit));
           recordStepGasCost(BigInt(unwrap(populated.gasLimit)) * BigInt(gasPrice));
+          this.attemptSummary.gas_estimate = unwrap(populated.gasLimit).toString();
+          this.attemptSummary.nonce = unwrap(populated.nonce);
           return populated;
         },
       });
 
-      // send transaction
-      const txResponse = await this.metricRecorder.stepExecution({
+      // a doomed transaction would only waste the nonce until it's dropped
+      if (this.blockGasLimit == null) {
+        this.blockGasLimit = unwrap(await this.provider.getBlock("latest")).gasLimit;
+      }
+      const exceedsBlockLimit = BigInt(unwrap(populated.gasLimit)) > this.blockGasLimit;
+      this.metricExceedsBlockLimit.set(exceedsBlockLimit ? 1 : 0);
+      if (exceedsBlockLimit) {
+        throw new WatchdogError(
+          WatchdogErrorKind.estimation,
+          `Gas estimate ${populated.gasLimit} exceeds the block gas limit ${this.blockGasLimit}`
+        );
+      }
+
+      // send transactions, with consecutive nonces when several are kept in flight
+      stage = WatchdogErrorKind.send;
+      // the ordering is checked on every attempt with several transactions in flight, otherwise on a cycle out of
+      // `ORDERING_CHECK_EVERY`. Retries of the cycle keep the same count, for the resent transactions to match
+      const orderingCheck = ORDERING_CHECK_EVERY > 0 && (this.cycle - 1) % ORDERING_CHECK_EVERY === 0;
+      const txCount = CONCURRENCY === 1 && orderingCheck ? 2 : CONCURRENCY;
+      if (tx.customData != null) {
+        warnIfClockSkewExceeded(this.logger);
+      }
+      if (ESTIMATE_SEND_DELAY_MS > 0) {
+        // the fees and nonce get stale under fast block production, the send shows how the node handles it
+        await timeoutPromise(ESTIMATE_SEND_DELAY_MS);
+      }
+      if (ALIGN_TO_BLOCKS) {
+        await this.alignToNextBlock();
+      }
+      const txResponses = await this.metricRecorder.stepExecution({
         stepName: "send",
         stepTimeoutMs: 10 * SEC,
-        fn: () => this.wallet.sendTransaction(populated),
+        fn: async () => {
+          const send = async (tx: typeof populated, index: number) => {
+            // A previous attempt that timed out may still be pending at the same nonce. Sending with freshly
+            // estimated fees is then rejected as replacement underpriced, so we bump the fees until it's accepted.
+            for (let bumps = 0; ; bumps++) {
+              try {
+                sendOrigin = "client";
+                const bumped = bumpFees(tx, UNDERPRICED_FEE_BUMP_PERCENT, bumps);
+                // signed separately to tell the client-side signing cost (e.g. KMS) apart from the network round-trip
+                const signingStart = Date.now();
+                const signedTx = await this.wallet.signTransaction(bumped);
+                signedTxs[index] = signedTx;
+                const signingEnd = Date.now();
+                const signingLatency = (signingEnd - signingStart) / 1000;
+                this.metricRecorder.manualRecordStepCompletion("signing", signingLatency, signingEnd / 1000);
+                sendOrigin = "node";
+                if (this.useDetailedOutput) {
+                  return await this.sendWithDetailedOutput(signedTx);
+                }
+                return await this.provider.broadcastTransaction(signedTx);
+              } catch (error) {
+                if (bumps >= UNDERPRICED_BUMP_LIMIT || !isReplacementUnderpriced(error)) {
+                  throw error;
+                }
+                this.metricRecorder.recordUnderpricedBump();
+                this.logger.warn(
+                  `Replacement transaction underpriced, bumping fees by ${UNDERPRICED_FEE_BUMP_PERCENT}% (bump ${bumps + 1} of ${UNDERPRICED_BUMP_LIMIT})`
+                );
+              }
+            }
+          };
+          // broadcast in nonce order (nodes may reject nonce gaps), only the confirmations are awaited concurrently
+          const responses = [];
+          let nextNonce = unwrap(populated.nonce);
+          for (let i = 0; i < txCount; i++) {
+            // a retry after an ambiguous failure (did it land?) sends the exact same transaction again, so that it
+            // can't result in a second transfer
+            const previous = this.retrySignedTxs?.[i];
+            // the transactions not sent by the failed attempt follow the resent ones, whose nonces "latest" may
+            // already have moved past if they were mined, which would otherwise open a nonce gap
+            const nonce = previous != null ? types.Transaction.from(previous).nonce : nextNonce;
+            nextNonce = nonce + 1;
+            const sentAtSec = Math.floor(Date.now() / 1000);
+            let response;
+            if (previous != null) {
+              signedTxs[i] = previous;
+              try {
+                sendOrigin = "node";
+                response = await this.resend(previous);
+              } catch (error) {
+                resendRejected = true;
+                throw error;
+              }
+            } else {
+              response = await send({ ...populated, nonce }, i);
+            }
+            this.sentTxHashes.add(response.hash);
+            sentTxs.push({ hash: response.hash, nonce, sentAtSec });
+            responses.push(response);
+          }
+          return responses;
+        },
       });
 
-      // wait for transaction
-      await this.metricRecorder.stepExecution({
+      this.metricSendOutcome.inc({ delay_ms: ESTIMATE_SEND_DELAY_MS, outcome: "success" });
+
+      // wait for transactions
+      stage = WatchdogErrorKind.receipt;
+      const executionStart = Date.now();
+      const receipts = await this.metricRecorder.stepExecution({
         stepName: "execution",
-        stepTimeoutMs: L2_EXECUTION_TIMEOUT,
+        stepTimeoutMs: MAX_MEMPOOL_WAIT_SECONDS != null ? MAX_MEMPOOL_WAIT_SECONDS * SEC : L2_EXECUTION_TIMEOUT,
         fn: async ({ recordStepGas, recordStepGasPrice, recordStepGasCost, timeoutMs }) => {
-          let receipt;
-          if (this.l2EthersProvider != null) {
-            receipt = unwrap(await this.l2EthersProvider.waitForTransaction(txResponse.hash, 1, timeoutMs));
-          } else {
-            // NOTE: bypasses txResponse.wait — it calls provider.waitForTransaction
-            // WITHOUT a timeout, so on step timeout the poller would leak.
-            receipt = unwrap(await this.provider.waitForTransaction(txResponse.hash, 1, timeoutMs));
+          const receipts = await Promise.all(
+            txResponses.map(async (txResponse) => {
+              if (this.wsProvider != null) {
+                return unwrap(await this.wsProvider.waitForTransaction(txResponse.hash, 1, timeoutMs));
+              }
+              if (this.l2EthersProvider != null) {
+                return unwrap(await this.l2EthersProvider.waitForTransaction(txResponse.hash, 1, timeoutMs));
+              }
+              // NOTE: bypasses txResponse.wait — it calls provider.waitForTransaction
+              // WITHOUT a timeout, so on step timeout the poller would leak.
+              return unwrap(await this.provider.waitForTransaction(txResponse.hash, 1, timeoutMs));
+            })
+          );
+          const reverted = receipts.find((receipt) => receipt.status !== 1);
+          if (reverted != null) {
+            included = true;
+            throw new WatchdogError(WatchdogErrorKind.revert, `Tx ${reverted.hash} reverted`);
+          }
+          // aggregated over the concurrent transactions. The extra transaction of an ordering check isn't, for the
+          // figures not to spike on those cycles, but it still counts towards the spend budget
+          let gasUsed = 0n;
+          let cost = 0n;
+          for (const [i, receipt] of receipts.entries()) {
+            const receiptCost = BigInt(unwrap(receipt.gasUsed)) * BigInt(unwrap(receipt.gasPrice));
+            if (i >= CONCURRENCY) {
+              recordSpend(receiptCost);
+              continue;
+            }
+            gasUsed += BigInt(unwrap(receipt.gasUsed));
+            cost += receiptCost;
           }
-          recordStepGas(unwrap(receipt.gasUsed));
-          recordStepGasPrice(unwrap(receipt.gasPrice));
-          recordStepGasCost(BigInt(unwrap(receipt.gasUsed)) * BigInt(unwrap(receipt.gasPrice)));
-          return receipt;
+          recordStepGas(gasUsed);
+          recordStepGasPrice(unwrap(receipts[receipts.length - 1].gasPrice));
+          recordStepGasCost(cost);
+          this.metricRecorder.recordTxCost(cost);
+          this.attemptSummary.gas_used = gasUsed.toString();
+          this.attemptSummary.cost = cost.toString();
+          this.attemptSummary.block_number = receipts[receipts.length - 1].blockNumber;
+          // the concurrent transactions are identical, so any of them can be compared to the estimate
+          const lastGasUsed = BigInt(unwrap(receipts[receipts.length - 1].gasUsed));
+          if (ESTIMATE_FEE_ACCURACY && estimateFeeGasLimit != null && lastGasUsed > 0n) {
+            this.metricEstimateFeeRatio.set(Number((estimateFeeGasLimit * 10_000n) / lastGasUsed) / 10_000);
+          }
+          return receipts;
         },
       }); // included in a block
+      included = true;
+      this.metricInclusionLatency.observe({ data_size: this.dataSize }, (Date.now() - executionStart) / 1000);
+      this.checkReceiptConsistency(receipts, signedTxs);
+      if (receipts.length > 1) this.checkOrdering(receipts);
+      if (MAX_MEMPOOL_WAIT_SECONDS != null) this.metricSlaMet.set(1);
+      await this.recordFeeHeadroom(signedTxs[signedTxs.length - 1], receipts[receipts.length - 1].blockNumber);
+
+      if (CONFIRMATION_MODE === "safe") {
+        const confirmationStart = Date.now();
+        await this.metricRecorder.stepExecution({
+          stepName: "finalization",
+          stepTimeoutMs: FINALIZATION_TIMEOUT,
+          fn: async ({ signal }) => {
+            for (const receipt of receipts) {
+              await this.waitForFinalization(receipt.hash, receipt.blockNumber, signal);
+            }
+          },
+        });
+        this.metricConfirmationLatency.set({ tag: this.confirmationTag }, (Date.now() - confirmationStart) / 1000);
+        const hashes = receipts.map((receipt) => receipt.hash).join(", ");
+        this.logger.info(`Tx ${hashes} confirmed by the ${this.confirmationTag} block`);
+      }
 
+      if (this.trackL2Finality) {
+        // batches are executed in order, so the batch of the last transaction covers the previous ones
+        const lastHash = receipts[receipts.length - 1].hash;
+        const batchNumber = await this.metricRecorder.stepExecution({
+          stepName: "l2_finality",
+          stepTimeoutMs: FINALIZATION_TIMEOUT,
+          fn: ({ signal }) => this.waitForBatchExecution(lastHash, signal),
+        });
+        const finalityLatency = (Date.now() - executionStart) / 1000;
+        this.metricL2FinalityLatency.set(finalityLatency);
+        this.logger.info(`Tx ${lastHash} final on L1 (batch ${batchNumber} executed) ${finalityLatency}s after send`);
+      }
+
+      if (EVENT_CONTRACT != null) {
+        // catches nodes that mine transactions but don't index or serve their logs
+        const missing = receipts.filter(
+          (receipt) => !receipt.logs.some((log) => log.address.toLowerCase() === EVENT_CONTRACT.toLowerCase())
+        );
+        this.metricEventEmitted.set(missing.length === 0 ? 1 : 0);
+        for (const receipt of missing) {
+          this.logger.error(`Receipt of ${receipt.hash} doesn't contain an event emitted by ${EVENT_CONTRACT}`);
+        }
+      }
+
+      if (usePaymaster) this.recordPaymasterResult(true);
+      this.sentTxHashes.clear();
+      this.retrySignedTxs = null;
       this.metricRecorder.recordFlowSuccess();
       return StatusNoSkip.OK;
       // eslint-disable-next-line @typescript-eslint/no-explicit-any
     } catch (error: any) {
-      this.logger.error("simple tx error: " + error?.message, error?.stack);
+      const watchdogError = toWatchdogError(error, stage);
+      this.attemptSummary.error_kind = watchdogError.kind;
+      if (stage === WatchdogErrorKind.send) {
+        this.metricSendOutcome.inc({ delay_ms: ESTIMATE_SEND_DELAY_MS, outcome: "failure" });
+        this.metricSendFailure.inc({ origin: sendOrigin });
+      }
+      // not included within the deadline, the retry broadcasts it again instead of waiting any longer
+      if (MAX_MEMPOOL_WAIT_SECONDS != null && watchdogError.kind === WatchdogErrorKind.receipt) {
+        this.metricSlaMet.set(0);
+      }
+      if (usePaymaster && isPaymasterError(error)) this.recordPaymasterResult(false);
+      this.logger.error(`simple tx ${watchdogError.kind} error: ${error?.message}`, error?.stack);
+      // only an ambiguous send or receipt timeout (did it land?) keeps the transactions for the next attempt. Rejected
+      // outright or included (e.g. reverted), there's nothing ambiguous about it and the next attempt starts over
+      const ambiguous = !included && (stage === WatchdogErrorKind.send || stage === WatchdogErrorKind.receipt);
+      this.retrySignedTxs = ambiguous && signedTxs.length > 0 && !resendRejected ? signedTxs : null;
+      if (DATA_SIZE_SHRINK && this.dataSize > 0 && classifyRpcError(error) === "payload_too_large") {
+        this.dataSize = Math.floor(this.dataSize / 2);
+        this.logger.warn(`Payload rejected as too large, shrinking the data size to ${this.dataSize} bytes`);
+      }
+      for (const tx of sentTxs) {
+        await this.checkExternalReplacement(tx.hash, tx.nonce, tx.sentAtSec);
+      }
       this.metricRecorder.recordFlowFailure();
+      if (EXIT_ON_REVERT && watchdogError.kind === WatchdogErrorKind.revert) {
+        // the orchestrator restarts or alerts on it, instead of the watchdog carrying on with failure metrics
+        throw new FatalError("Transfer reverted and EXIT_ON_REVERT is set", watchdogError);
+      }
       return StatusNoSkip.FAIL;
     }
   }
 
+  /**
+   * Attaches the access list generated by the node to the transaction, reporting its size and the gas it saves
+   * compared to a plain estimate. Falls back to the plain transaction if the node doesn't support the method.
+   */
+  private async withAccessList(tx: types.TransactionRequest): Promise<types.TransactionRequest> {
+    const rpcTx = this.provider.getRpcTransaction({ ...tx, from: this.wallet.address });
+    let result: { accessList: { address: string; storageKeys: string[] }[]; gasUsed: string };
+    try {
+      result = await this.provider.send("eth_createAccessList", [rpcTx, ESTIMATE_BLOCK_TAG ?? "latest"]);
+    } catch (error) {
+      if (classifyRpcError(error) !== "method_not_found") {
+        throw error;
+      }
+      this.logger.warn("eth_createAccessList is not supported, sending transactions without access list");
+      this.useAccessList = false;
+      return tx;
+    }
+    const plainGas = await this.provider.estimateGas({ ...tx, from: this.wallet.address });
+    this.metricAccessListEntries.set(result.accessList.length);
+    this.metricAccessListGasSaved.set(Number(plainGas - BigInt(result.gasUsed)));
+    return { ...tx, type: 2, accessList: result.accessList };
+  }
+
+  /**
+   * Waits until the expected seal of the next block, so that where in the block interval the transaction is
+   * submitted doesn't add noise to the inclusion latency. The block time is the average over the last blocks.
+   */
+  private async alignToNextBlock() {
+    const latest = unwrap(await this.provider.getBlock("latest"));
+    const earlier = unwrap(await this.provider.getBlock(Math.max(0, latest.number - ALIGN_SAMPLE_BLOCKS)));
+    const blockTimeSec = (latest.timestamp - earlier.timestamp) / Math.max(1, latest.number - earlier.number);
+    if (blockTimeSec <= 0) {
+      // sub-second block times can't be learned from timestamps in seconds
+      return;
+    }
+    const sinceLatestSec = Date.now() / 1000 - latest.timestamp;
+    const nextBlockSec = latest.timestamp + Math.max(1, Math.ceil(sinceLatestSec / blockTimeSec)) * blockTimeSec;
+    const waitMs = Math.min(nextBlockSec * 1000 - Date.now(), blockTimeSec * SEC);
+    if (waitMs > 0) {
+      await timeoutPromise(waitMs);
+    }
+    const offsetSec = Date.now() / 1000 - nextBlockSec;
+    this.logger.debug(`Sending ${offsetSec.toFixed(2)} seconds after the expected block, block time ${blockTimeSec}`);
+    this.metricSendBlockOffset.set(offsetSec);
+  }
+
+  private async estimateGasAt(tx: types.TransactionRequest, blockTag: string): Promise<bigint> {
+    const rpcTx = this.provider.getRpcTransaction({ ...tx, from: this.wallet.address });
+    return BigInt(await this.provider.send("eth_estimateGas", [rpcTx, blockTag]));
+  }
+
+  /** Broadcasts a transaction of a failed attempt again, a duplicate rejection means the original reached the node. */
+  private async resend(signedTx: string): Promise<{ hash: string }> {
+    try {
+      return await this.provider.broadcastTransaction(signedTx);
+    } catch (error) {
+      const hash = unwrap(types.Transaction.from(signedTx).hash);
+      const provider: EthersProvider = this.l2EthersProvider ?? this.provider;
+      // a used nonce only means the original landed if it's the transaction mined at it, not a third-party one
+      const landed =
+        isDuplicateTransaction(error) ||
+        (isNonceTooLow(error) && (await provider.getTransactionReceipt(hash)) != null);
+      if (!landed) {
+        throw error;
+      }
+      this.metricDuplicateRejected.inc();
+      this.logger.warn(`Retried tx ${hash} rejected as duplicate, waiting for the original`);
+      return { hash };
+    }
+  }
+
+  /**
+   * Verifies that each receipt matches the signed transaction it was fetched for, against nodes returning wrong or
+   * mismatched receipts, which the status of the transfer alone doesn't reveal.
+   */
+  private checkReceiptConsistency(
+    receipts: { hash: string; from: string; to: string | null; gasUsed: bigint }[],
+    signedTxs: string[]
+  ) {
+    const violations: string[] = [];
+    receipts.forEach((receipt, i) => {
+      const tx = types.Transaction.from(signedTxs[i]);
+      if (receipt.hash !== tx.hash) {
+        violations.push(`receipt hash ${receipt.hash} differs from the sent tx ${tx.hash}`);
+      }
+      if (receipt.from.toLowerCase() !== this.wallet.address.toLowerCase()) {
+        violations.push(`receipt of ${tx.hash} is from ${receipt.from} instead of ${this.wallet.address}`);
+      }
+      if (receipt.to?.toLowerCase() !== tx.to?.toLowerCase()) {
+        violations.push(`receipt of ${tx.hash} is to ${receipt.to} instead of ${tx.to}`);
+      }
+      if (receipt.gasUsed > tx.gasLimit) {
+        violations.push(`receipt of ${tx.hash} used ${receipt.gasUsed} gas, more than its gas limit ${tx.gasLimit}`);
+      }
+    });
+    this.metricReceiptConsistent.set(violations.length === 0 ? 1 : 0);
+    for (const violation of violations) {
+      this.logger.error(`Inconsistent receipt: ${violation}`);
+    }
+  }
+
+  /**
+   * Reports whether transactions sent at consecutive nonces (in nonce order) were mined in nonce order, a
+   * correctness property of the sequencer that probes of a single transaction can't observe.
+   */
+  private checkOrdering(receipts: { hash: string; blockNumber: number; index: number }[]) {
+    const misordered = receipts.findIndex(
+      (receipt, i) =>
+        i > 0 &&
+        (receipt.blockNumber < receipts[i - 1].blockNumber ||
+          (receipt.blockNumber === receipts[i - 1].blockNumber && receipt.index < receipts[i - 1].index))
+    );
+    this.metricOrderingCorrect.set(misordered === -1 ? 1 : 0);
+    if (misordered !== -1) {
+      const [previous, next] = [receipts[misordered - 1], receipts[misordered]];
+      this.logger.error(
+        `Tx ${next.hash} was mined before ${previous.hash} of the previous nonce ` +
+          `(block ${next.blockNumber} index ${next.index} vs block ${previous.blockNumber} index ${previous.index})`
+      );
+    }
+  }
+
+  /// a headroom barely above 1 explains slow inclusion, the transaction only fits blocks that don't raise the base fee
+  private async recordFeeHeadroom(signedTx: string, blockNumber: number) {
+    try {
+      const tx = types.Transaction.from(signedTx);
+      const maxFee = tx.maxFeePerGas ?? tx.gasPrice;
+      const baseFee = unwrap(await this.provider.getBlock(blockNumber)).baseFeePerGas;
+      if (maxFee == null || baseFee == null || baseFee === 0n) return;
+      // scaled to keep 4 decimals before converting to a float
+      this.metricFeeHeadroom.set(Number((maxFee * 10_000n) / baseFee) / 10_000);
+      // eslint-disable-next-line @typescript-eslint/no-explicit-any
+    } catch (error: any) {
+      this.logger.warn(`Failed to record the fee headroom: ${error?.message}`);
+    }
+  }
+
+  /**
+   * Reports when a transaction of ours didn't get mined because another process sharing the wallet used its nonce,
+   * as opposed to it being stuck or reverted. Looks for the mined transaction in the blocks since ours was sent.
+   */
+  private async checkExternalReplacement(txHash: string, nonce: number, sentAtSec: number) {
+    const provider: EthersProvider = this.l2EthersProvider ?? this.provider;
+    try {
+      if ((await provider.getTransactionReceipt(txHash)) != null) return;
+      if ((await provider.getTransactionCount(this.wallet.address, "latest")) <= nonce) return;
+      const from = this.wallet.address.toLowerCase();
+      for (let blockNumber = await provider.getBlockNumber(); blockNumber >= 0; blockNumber--) {
+        const block = unwrap(await provider.getBlock(blockNumber, true));
+        const mined = block.prefetchedTransactions.find((tx) => tx.from.toLowerCase() === from && tx.nonce === nonce);
+        if (mined != null) {
+          if (!this.sentTxHashes.has(mined.hash)) {
+            this.metricReplacedExternally.inc();
+            this.logger.error(`Tx ${txHash} was replaced by ${mined.hash} (nonce ${nonce}) not sent by the watchdog`);
+          }
+          return;
+        }
+        if (block.timestamp < sentAtSec) return;
+      }
+      // eslint-disable-next-line @typescript-eslint/no-explicit-any
+    } catch (error: any) {
+      this.logger.warn(`Failed to check whether tx ${txHash} was replaced: ${error?.message}`);
+    }
+  }
+
+  /// a node without support for the configured tag is switched to `latest` for the rest of the run
+  private async getConfirmationBlock(provider: EthersProvider) {
+    try {
+      const block = await provider.getBlock(this.confirmationTag);
+      if (block != null || this.confirmationTag === "latest") return block;
+      // eslint-disable-next-line @typescript-eslint/no-explicit-any
+    } catch (error: any) {
+      if (this.confirmationTag === "latest" || isTransportError(error)) throw error;
+    }
+    this.logger.warn(`Block tag ${this.confirmationTag} is not supported, confirming against latest instead`);
+    this.confirmationTag = "latest";
+    return await provider.getBlock("latest");
+  }
+
+  /// waits until the L1 batch of the transaction is executed on L1 (`executedAt` in `zks_getL1BatchDetails`), which
+  /// is the L1-backed finality of the transaction, and returns the batch number
+  private async waitForBatchExecution(txHash: string, signal: AbortSignal): Promise<number> {
+    let batchNumber: number | null = null;
+    while (!signal.aborted) {
+      // only known once the batch of the transaction is sealed
+      if (batchNumber == null) {
+        batchNumber = (await this.provider.getTransactionReceipt(txHash))?.l1BatchNumber ?? null;
+      }
+      if (batchNumber != null) {
+        const details = await this.provider.send("zks_getL1BatchDetails", [batchNumber]);
+        if (details?.executedAt != null) return batchNumber;
+      }
+      await timeoutPromise(10 * SEC);
+    }
+    throw new Error(`Batch of tx ${txHash} not executed in time`);
+  }
+
+  /// waits until the confirmation block (`finalized` by default) reaches the transaction's block, then re-checks the
+  /// receipt against reorgs
+  private async waitForFinalization(txHash: string, blockNumber: number, signal: AbortSignal) {
+    const provider: EthersProvider = this.l2EthersProvider ?? this.provider;
+    while (!signal.aborted) {
+      const confirmed = await this.getConfirmationBlock(provider);
+      if (confirmed != null && confirmed.number >= blockNumber) {
+        const receipt = await provider.getTransactionReceipt(txHash);
+        if (receipt?.blockNumber !== blockNumber) {
+          throw new Error(`Tx ${txHash} is no longer in block ${blockNumber} once confirmed by ${this.confirmationTag}`);
+        }
+        return;
+      }
+      await timeoutPromise(5 * SEC);
+    }
+  }
+
   public async run() {
+    // only ZKsync transactions depend on the fee model, plain signers must not call any zks_ method
+    this.feeModelVersion = this.wallet instanceof ZkSyncWallet ? await detectFeeModelVersion(this.provider) : null;
     while (true) {
-      const nextExecutionWait = timeoutPromise(this.intervalMs);
+      const waitNextExecution = this.startCycle(this.intervalMs);
+      const cycleStart = Date.now();
+      let status: StatusNoSkip = StatusNoSkip.FAIL;
+      let attempts = 0;
+      // transactions of the previous cycle are either mined or dropped by now
+      this.retrySignedTxs = null;
+      // one paymaster per cycle, so that each of them is probed every `paymasterAddresses.length` cycles
+      if (this.paymasterAddresses.length > 0) {
+        this.paymasterAddress = this.paymasterAddresses[this.cycle % this.paymasterAddresses.length];
+      }
+      this.cycle++;
+      if (isSpendBudgetExhausted()) {
+        this.logger.warn("Spend budget exhausted, skipping the transfer");
+        this.metricRecorder.recordFlowStart();
+        this.metricRecorder.recordFlowSkipped();
+        await waitNextExecution();
+        continue;
+      }
       // Record L2 balance before each cycle
       const l2Balance = await this.provider.getBalance(this.wallet.address);
       recordL2BaseTokenBalance(l2Balance);
+      requestTopUpIfLow(this.wallet.address, l2Balance);
+      await checkChainIdUnchanged(this.provider);
       for (let i = 0; i < TRANSFER_RETRY_LIMIT; i++) {
-        const result = await this.l2WalletLock.withLock(() => this.step());
+        // the first attempt of each cycle always uses the paymaster, so that its recovery is detected
+        const usePaymaster =
+          this.paymasterAddress != null && (i === 0 || !PAYMASTER_FALLBACK || !this.isPaymasterDegraded());
+        await waitForRateLimitCooldown();
+        const result = await this.l2WalletLock.withLock(() => this.step(usePaymaster));
+        status = result;
+        attempts = i + 1;
         if (result === StatusNoSkip.OK) {
           this.logger.info(`attempt ${i + 1} succeeded`);
           break;
@@ -127,8 +896,18 @@ export class SimpleTxFlow extends BaseFlow {
         }
         await timeoutPromise(TRANSFER_RETRY_INTERVAL);
       }
+      this.consecutiveSuccesses = status === StatusNoSkip.OK ? this.consecutiveSuccesses + 1 : 0;
+      this.metricConsecutiveSuccesses.set(this.consecutiveSuccesses);
+      // one line digest of the cycle, complementing the per-stage logs (figures are of the last attempt)
+      this.logger.info("Cycle summary", {
+        ...this.attemptSummary,
+        ...(this.paymasterAddress != null && { paymaster: this.paymasterAddress }),
+        total_latency: (Date.now() - cycleStart) / 1000,
+        status,
+        attempts,
+      });
       //sleep
-      await nextExecutionWait;
+      await waitNextExecution();
     }
   }
 }
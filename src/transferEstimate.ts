@@ -0,0 +1,66 @@
+import "dotenv/config";
+import { Gauge } from "prom-client";
+
+import { BaseFlow } from "./baseFlow";
+import { SEC } from "./utils";
+
+import type { Provider as EthersProvider } from "ethers";
+
+const FLOW_NAME = "transfer_estimate";
+
+/**
+ * Estimates the gas of the 1 wei self-transfer of the transfer flow without ever sending it. Estimation is free,
+ * so it can run far more often than the full send and gives a high-resolution health signal of the node.
+ */
+export class TransferEstimateFlow extends BaseFlow {
+  /// own metric family, so that the frequent estimates are kept apart from the full send metrics in dashboards
+  private metricEstimateLatency: Gauge;
+  private metricEstimateStatus: Gauge;
+
+  constructor(
+    private provider: EthersProvider,
+    private address: string,
+    private intervalMs: number
+  ) {
+    super(FLOW_NAME);
+    this.metricEstimateLatency = new Gauge({
+      name: "watchdog_estimate_latency",
+      help: "Seconds taken by the last estimate-only check of the transfer",
+    });
+    this.metricEstimateStatus = new Gauge({
+      name: "watchdog_estimate_status",
+      help: "1 if the last estimate-only check of the transfer succeeded, 0 if it failed",
+    });
+  }
+
+  public async run() {
+    while (true) {
+      const waitNextExecution = this.startCycle(this.intervalMs);
+
+      try {
+        this.metricRecorder.recordFlowStart();
+
+        const start = Date.now();
+        await this.metricRecorder.stepExecution({
+          stepName: "estimation",
+          stepTimeoutMs: 10 * SEC,
+          fn: async ({ recordStepGas }) => {
+            const gas = await this.provider.estimateGas({ from: this.address, to: this.address, value: 1 });
+            recordStepGas(gas);
+          },
+        });
+        this.metricEstimateLatency.set((Date.now() - start) / 1000);
+        this.metricEstimateStatus.set(1);
+
+        this.metricRecorder.recordFlowSuccess();
+        // eslint-disable-next-line @typescript-eslint/no-explicit-any
+      } catch (error: any) {
+        this.metricEstimateStatus.set(0);
+        this.logger.error("Transfer estimation error: " + error?.message, error?.stack);
+        this.metricRecorder.recordFlowFailure();
+      }
+
+      await waitNextExecution();
+    }
+  }
+}
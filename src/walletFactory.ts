@@ -1,9 +1,11 @@
-import { Wallet as EthersWallet } from "ethers";
+import { Wallet as EthersWallet, getAddress, isAddress } from "ethers";
 import winston from "winston";
 import { Wallet as ZkSyncWallet } from "zksync-ethers";
 
+import { ExternalSignerZkSyncWallet } from "./externalSignerZkSyncWallet";
 import { GcpKmsSigner } from "./gcpKmsSigner";
-import { GcpKmsZkSyncWallet } from "./gcpKmsZkSyncWallet";
+import { RemoteSigner } from "./remoteSigner";
+import { SEC, unwrap } from "./utils";
 
 import type { ethers } from "ethers";
 import type { Provider } from "zksync-ethers";
@@ -16,25 +18,123 @@ export function isKmsKey(walletKey: string): boolean {
   return walletKey.startsWith("projects/");
 }
 
-// ---- Ethers-level signer (for Prividium / ZKOS simple transfers) ------------
+/// JSON-RPC signer that signs for wallet keys given as a plain address
+const REMOTE_SIGNER_URL = process.env.REMOTE_SIGNER_URL;
 
 /**
- * Creates an ethers `Signer` from the wallet key.
+ * Returns `true` when `walletKey` is an address rather than a private key,
+ * i.e. signing for it is delegated to the remote signer at `REMOTE_SIGNER_URL`.
+ */
+export function isRemoteSignerKey(walletKey: string): boolean {
+  return isAddress(walletKey);
+}
+
+/// shared wallet key read from stdin with `WALLET_KEY_STDIN`, taking the place of `WALLET_KEY`
+let stdinWalletKey: string | null = null;
+/// startup fails instead of hanging if stdin is left open without the key being written to it
+const STDIN_READ_TIMEOUT = 10 * SEC;
+
+/**
+ * Reads the shared wallet key from the first line of stdin, so that it doesn't have to be in the environment, where
+ * it's visible in `/proc`. The buffers it was read into are zeroed, only the key string itself stays in memory.
+ * Fails if stdin is a terminal, or if no line is read within `STDIN_READ_TIMEOUT`.
+ */
+export async function readWalletKeyFromStdin() {
+  if (process.stdin.isTTY) {
+    throw new Error("WALLET_KEY_STDIN is set, but stdin is a terminal. Pipe the wallet key into the watchdog");
+  }
+  const chunks: Buffer[] = [];
+  try {
+    await new Promise<void>((resolve, reject) => {
+      const timeout = setTimeout(() => {
+        const seconds = STDIN_READ_TIMEOUT / SEC;
+        reject(new Error(`WALLET_KEY_STDIN is set, but no wallet key was read from stdin within ${seconds} seconds`));
+      }, STDIN_READ_TIMEOUT);
+      const done = () => {
+        clearTimeout(timeout);
+        resolve();
+      };
+      process.stdin.on("data", (chunk: Buffer) => {
+        chunks.push(chunk);
+        if (chunk.includes(0x0a)) done();
+      });
+      process.stdin.once("end", done);
+      process.stdin.once("error", (error) => {
+        clearTimeout(timeout);
+        reject(error);
+      });
+    });
+  } finally {
+    // the rest of stdin isn't read, and must not keep the process alive
+    process.stdin.removeAllListeners();
+    process.stdin.destroy();
+  }
+  const buffer = Buffer.concat(chunks);
+  const newline = buffer.indexOf(0x0a);
+  const walletKey = buffer.subarray(0, newline === -1 ? buffer.length : newline).toString("utf8").trim();
+  buffer.fill(0);
+  chunks.forEach((chunk) => chunk.fill(0));
+  if (walletKey === "") {
+    throw new Error("WALLET_KEY_STDIN is set, but no wallet key was read from stdin");
+  }
+  stdinWalletKey = walletKey;
+  winston.info("Read the wallet key from stdin");
+}
+
+/**
+ * Returns the key of the wallet used by a group of flows, falling back to the shared `WALLET_KEY` (or the key read
+ * from stdin):
+ *
+ * - `WALLET_KEY_DEFAULT` → transfer, withdrawal (and its finalization), Prividium and Safe proposal flows
+ * - `WALLET_KEY_DEPOSIT` → deposit flows
+ *
+ * Separate wallets keep the flows from contending for the same nonce.
+ */
+export function getWalletKey(flowWallet: "DEFAULT" | "DEPOSIT"): string {
+  return unwrap(process.env[`WALLET_KEY_${flowWallet}`] || stdinWalletKey || process.env.WALLET_KEY);
+}
+
+/**
+ * Creates the signer for a wallet key whose private key is not held by the process,
+ * or returns `null` for a hex private key.
  *
- * - Hex key  → `ethers.Wallet`
  * - KMS name → `GcpKmsSigner`
+ * - Address  → `RemoteSigner`
  */
-export async function createEthersSigner(
+async function createExternalSigner(
   walletKey: string,
   provider?: ethers.Provider | null
-): Promise<EthersWallet | GcpKmsSigner> {
+): Promise<GcpKmsSigner | RemoteSigner | null> {
   if (isKmsKey(walletKey)) {
-    winston.info("Creating GCP KMS ethers signer");
+    winston.info("Creating GCP KMS signer");
     const signer = new GcpKmsSigner(walletKey, provider);
     await signer.getAddress(); // eagerly resolve & cache the address
     return signer;
   }
-  return new EthersWallet(walletKey, provider);
+  if (isRemoteSignerKey(walletKey)) {
+    if (!REMOTE_SIGNER_URL) {
+      throw new Error(`Wallet key ${walletKey} is an address, but REMOTE_SIGNER_URL is not set`);
+    }
+    winston.info(`Creating remote signer for ${walletKey} at ${new URL(REMOTE_SIGNER_URL).host}`);
+    return new RemoteSigner(getAddress(walletKey), REMOTE_SIGNER_URL, provider);
+  }
+  return null;
+}
+
+// ---- Ethers-level signer (for Prividium / ZKOS simple transfers) ------------
+
+/**
+ * Creates an ethers `Signer` from the wallet key.
+ *
+ * - Hex key  → `ethers.Wallet`
+ * - KMS name → `GcpKmsSigner`
+ * - Address  → `RemoteSigner`
+ */
+export async function createEthersSigner(
+  walletKey: string,
+  provider?: ethers.Provider | null
+): Promise<EthersWallet | GcpKmsSigner | RemoteSigner> {
+  return (await createExternalSigner(walletKey, provider)) ?? new EthersWallet(walletKey, provider);
 }
 
 // ---- ZkSync-level wallet (for deposits, withdrawals, full flows) ------------
@@ -42,17 +142,17 @@ export async function createEthersSigner(
 /**
  * Creates a ZkSync `Wallet` from the wallet key.
  *
- * - Hex key  → `ZkSyncWallet`
- * - KMS name → `GcpKmsZkSyncWallet` (async factory)
+ * - Hex key             → `ZkSyncWallet`
+ * - KMS name or address → `ExternalSignerZkSyncWallet` (async factory)
  */
 export async function createZkSyncWallet(
   walletKey: string,
   providerL2?: Provider,
   providerL1?: ethers.Provider
 ): Promise<ZkSyncWallet> {
-  if (isKmsKey(walletKey)) {
-    winston.info("Creating GCP KMS ZkSync wallet");
-    return await GcpKmsZkSyncWallet.create(walletKey, providerL2, providerL1);
+  const externalSigner = await createExternalSigner(walletKey, providerL2);
+  if (externalSigner != null) {
+    return await ExternalSignerZkSyncWallet.create(externalSigner, providerL2, providerL1);
   }
   return new ZkSyncWallet(walletKey, providerL2, providerL1);
 }
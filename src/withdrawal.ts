@@ -2,6 +2,8 @@ import "dotenv/config";
 
 import { L2_EXECUTION_TIMEOUT } from "./configs";
 import { StatusNoSkip } from "./flowMetric";
+import { checkChainIdUnchanged } from "./rpcLoggingProvider";
+import { isSpendBudgetExhausted } from "./spendBudget";
 import { SEC, unwrap, timeoutPromise } from "./utils";
 import { WITHDRAWAL_RETRY_INTERVAL, WITHDRAWAL_RETRY_LIMIT, WithdrawalBaseFlow, STEPS } from "./withdrawalBase";
 
@@ -27,11 +29,11 @@ export class WithdrawalFlow extends WithdrawalBaseFlow {
     try {
       this.metricRecorder.recordFlowStart();
 
-      const populatedWithOverrides = await this.metricRecorder.stepExecution({
+      const populatedWithOverrides = await this.metricRecorder.estimationStepExecution({
         stepName: STEPS.estimation,
         stepTimeoutMs: 10 * SEC,
         fn: async ({ recordStepGas, recordStepGasPrice, recordStepGasCost }) => {
-          const tx = await this.wallet._providerL2().getWithdrawTx(this.getWithdrawalRequest());
+          const tx = await this.getWithdrawTx();
           const nonce = await this.wallet.getNonce("latest");
           const populated = await this.wallet.populateTransaction({
             ...tx,
@@ -72,7 +74,9 @@ export class WithdrawalFlow extends WithdrawalBaseFlow {
           );
           recordStepGas(unwrap(receipt.gasUsed));
           recordStepGasPrice(unwrap(receipt.gasPrice));
-          recordStepGasCost(BigInt(unwrap(receipt.gasUsed)) * BigInt(unwrap(receipt.gasPrice)));
+          const cost = BigInt(unwrap(receipt.gasUsed)) * BigInt(unwrap(receipt.gasPrice));
+          recordStepGasCost(cost);
+          this.metricRecorder.recordTxCost(cost);
         },
       });
 
@@ -87,6 +91,7 @@ export class WithdrawalFlow extends WithdrawalBaseFlow {
   }
 
   public async run() {
+    await this.detectFeeModel();
     const lastExecution = await this.getLastExecution("latest", this.wallet.address);
     const currentBlockchainTimestamp = await this.getCurrentChainTimestamp();
     const timeSinceLastWithdrawalSec = currentBlockchainTimestamp - (lastExecution?.timestampL2 ?? 0);
@@ -97,7 +102,15 @@ export class WithdrawalFlow extends WithdrawalBaseFlow {
       await timeoutPromise(waitTime);
     }
     while (true) {
-      const nextExecutionWait = timeoutPromise(this.intervalMs);
+      const waitNextExecution = this.startCycle(this.intervalMs);
+      if (isSpendBudgetExhausted()) {
+        this.logger.warn("Spend budget exhausted, skipping the withdrawal");
+        this.metricRecorder.recordFlowStart();
+        this.metricRecorder.recordFlowSkipped();
+        await waitNextExecution();
+        continue;
+      }
+      await checkChainIdUnchanged(this.wallet._providerL2());
       for (let i = 0; i < WITHDRAWAL_RETRY_LIMIT; i++) {
         const result = await this.l2WalletLock.withLock(() => this.executeWatchdogWithdrawal());
         if (result === StatusNoSkip.FAIL) {
@@ -113,7 +126,7 @@ export class WithdrawalFlow extends WithdrawalBaseFlow {
           break;
         }
       }
-      await nextExecutionWait;
+      await waitNextExecution();
     }
   }
 }
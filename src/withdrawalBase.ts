@@ -4,11 +4,12 @@ import { IEthToken__factory as IETHTokenFactory } from "zksync-ethers/build/type
 import { L2_BASE_TOKEN_ADDRESS } from "zksync-ethers/build/utils";
 
 import { BaseFlow } from "./baseFlow";
+import { detectFeeModelVersion } from "./feeModel";
 import { SEC, unwrap } from "./utils";
 
 import type { BigNumberish, ethers, TransactionReceipt } from "ethers";
 import type { types, Wallet } from "zksync-ethers";
-import type { PaymasterParams } from "zksync-ethers/build/types";
+import type { FinalizeWithdrawalParams, PaymasterParams } from "zksync-ethers/build/types";
 
 export type WithdrawalTxRequest = {
   token: types.Address;
@@ -37,8 +38,12 @@ export const STEPS = {
 
 export const WITHDRAWAL_RETRY_INTERVAL = +(process.env.FLOW_WITHDRAWAL_RETRY_INTERVAL ?? 30 * SEC);
 export const WITHDRAWAL_RETRY_LIMIT = +(process.env.FLOW_WITHDRAWAL_RETRY_LIMIT ?? 10);
+const PRE_V26_BRIDGES = process.env.PRE_V26_BRIDGES === "1";
 
 export abstract class WithdrawalBaseFlow extends BaseFlow {
+  /// only detected for paymaster withdrawals, the only ones with a gas per pubdata limit set by the watchdog
+  protected feeModelVersion: number | null = null;
+
   constructor(
     protected wallet: Wallet,
     protected paymasterAddress: string | undefined,
@@ -72,6 +77,30 @@ export abstract class WithdrawalBaseFlow extends BaseFlow {
     }
   }
 
+  /** To be called at the start of the flow loop. */
+  protected async detectFeeModel() {
+    if (this.paymasterAddress != null && !this.isZKsyncOS) {
+      this.feeModelVersion = await detectFeeModelVersion(this.wallet._providerL2());
+    }
+  }
+
+  /**
+   * The withdrawal transaction. With the V2 fee model, paymaster withdrawals use the gas per pubdata limit estimated
+   * by `zks_estimateFee`, the default limit only fits the fixed pubdata price of V1.
+   */
+  protected async getWithdrawTx(): Promise<types.TransactionRequest> {
+    const provider = this.wallet._providerL2();
+    const tx: types.TransactionRequest = await provider.getWithdrawTx({
+      from: this.wallet.address,
+      ...this.getWithdrawalRequest(),
+    });
+    if (tx.customData == null || this.feeModelVersion !== 2) {
+      return tx;
+    }
+    const fee = await provider.estimateFee({ ...tx, from: this.wallet.address });
+    return { ...tx, customData: { ...tx.customData, gasPerPubdata: fee.gasPerPubdataLimit } };
+  }
+
   protected async getLastExecution(
     blockType: "latest" | "finalized",
     wallet: string | undefined
@@ -104,6 +133,48 @@ export abstract class WithdrawalBaseFlow extends BaseFlow {
     };
   }
 
+  /** Estimates the L1 finalization of the withdrawal, which only succeeds once the withdrawal is finalizable. */
+  protected async simulateFinalization(chainId: BigNumberish, params: FinalizeWithdrawalParams) {
+    const { l1BatchNumber, l2MessageIndex, l2TxNumberInBlock, message, sender, proof } = params;
+    if (PRE_V26_BRIDGES) {
+      // Legacy shared bridge
+      const bridges = await this.wallet.getL1BridgeContracts();
+      await this.metricRecorder.stepExecution({
+        stepName: STEPS.l1_simulation,
+        stepTimeoutMs: 10 * SEC,
+        fn: async ({ recordStepGas }) => {
+          const gas = await bridges.shared.finalizeWithdrawal.estimateGas(
+            chainId,
+            l1BatchNumber as BigNumberish,
+            l2MessageIndex as BigNumberish,
+            l2TxNumberInBlock as BigNumberish,
+            message,
+            proof
+          );
+          recordStepGas(gas);
+        },
+      });
+    } else {
+      const l1Nullifier = await this.wallet.getL1Nullifier();
+      await this.metricRecorder.stepExecution({
+        stepName: STEPS.l1_simulation,
+        stepTimeoutMs: 10 * SEC,
+        fn: async ({ recordStepGas }) => {
+          const gas = await l1Nullifier.finalizeDeposit.estimateGas({
+            chainId,
+            l2BatchNumber: l1BatchNumber as BigNumberish,
+            l2MessageIndex: l2MessageIndex as BigNumberish,
+            l2Sender: sender,
+            l2TxNumberInBatch: l2TxNumberInBlock as BigNumberish,
+            message,
+            merkleProof: proof,
+          });
+          recordStepGas(gas);
+        },
+      });
+    }
+  }
+
   protected async getCurrentChainTimestamp(): Promise<number> {
     return unwrap(
       await this.wallet
@@ -0,0 +1,147 @@
+import "dotenv/config";
+
+import { Gauge } from "prom-client";
+
+import { L2_EXECUTION_TIMEOUT } from "./configs";
+import { WatchdogError, WatchdogErrorKind } from "./errors";
+import { Status } from "./flowMetric";
+import { checkChainIdUnchanged } from "./rpcLoggingProvider";
+import { isSpendBudgetExhausted } from "./spendBudget";
+import { pendingOperations } from "./stateStore";
+import { MIN, SEC } from "./utils";
+import { WithdrawalBaseFlow, STEPS } from "./withdrawalBase";
+
+import type { Mutex } from "./lock";
+import type { PendingOperation } from "./stateStore";
+import type { BigNumberish } from "ethers";
+import type { Wallet } from "zksync-ethers";
+
+const FLOW_NAME = "withdrawal_e2e";
+const OPERATION_TYPE = "withdrawal";
+/// max acceptable time from initiating the withdrawal on L2 until it's finalizable on L1
+const WITHDRAWAL_E2E_SLA = +(process.env.FLOW_WITHDRAWAL_E2E_SLA ?? 6 * 60 * MIN);
+
+/**
+ * Initiates a withdrawal and tracks it until it can be finalized on L1 (its finalization simulates
+ * successfully), measuring the end-to-end duration against an SLA. The wait takes hours, so the in-flight
+ * withdrawal is persisted in the state store and tracking resumes after a restart.
+ */
+export class WithdrawalE2eFlow extends WithdrawalBaseFlow {
+  private metricE2eLatency: Gauge;
+  private metricSlaMet: Gauge;
+  private chainId!: BigNumberish;
+
+  constructor(
+    wallet: Wallet,
+    private l2WalletLock: Mutex,
+    private intervalMs: number
+  ) {
+    super(wallet, undefined, false, FLOW_NAME);
+    this.metricE2eLatency = new Gauge({
+      name: "watchdog_withdrawal_e2e_latency",
+      help: "Seconds from initiating the last tracked withdrawal on L2 until it became finalizable on L1",
+    });
+    this.metricSlaMet = new Gauge({
+      name: "watchdog_withdrawal_sla_met",
+      help: "1 if the last tracked withdrawal became finalizable within the SLA, 0 once the SLA was exceeded",
+    });
+  }
+
+  private async initiateWithdrawal(): Promise<PendingOperation> {
+    const tx = await this.metricRecorder.stepExecution({
+      stepName: STEPS.send,
+      stepTimeoutMs: 10 * SEC,
+      fn: async () => this.wallet.sendTransaction(await this.getWithdrawTx()),
+    });
+    const operation = { type: OPERATION_TYPE, txHash: tx.hash, startedAt: Date.now() };
+    // persisted before waiting for anything, so that a restart keeps tracking it
+    pendingOperations.add(operation);
+    this.logger.info(`Tx (L2: ${tx.hash}) sent on L2, tracking it until finalizable`);
+    return operation;
+  }
+
+  /**
+   * Whether the withdrawal can be finalized on L1 yet, throws a `WatchdogError` if it will never be. Other errors
+   * (RPC failures, timeouts, a finalization simulation failing before the proof is available) are transient.
+   */
+  private async isFinalizable(operation: PendingOperation): Promise<boolean> {
+    const provider = this.wallet._providerL2();
+    const receipt = await provider.getTransactionReceipt(operation.txHash);
+    if (receipt == null) {
+      if (Date.now() - operation.startedAt > 10 * L2_EXECUTION_TIMEOUT) {
+        throw new WatchdogError(WatchdogErrorKind.receipt, `Withdrawal ${operation.txHash} was never included on L2`);
+      }
+      return false;
+    }
+    if (receipt.status !== 1) {
+      throw new WatchdogError(WatchdogErrorKind.revert, `Withdrawal ${operation.txHash} reverted on L2`);
+    }
+    const finalizedBlock = await provider.getBlock("finalized");
+    if (finalizedBlock == null || finalizedBlock.number < receipt.blockNumber) {
+      return false;
+    }
+    const params = await this.metricRecorder.stepExecution({
+      stepName: STEPS.get_finalization_params,
+      stepTimeoutMs: 10 * SEC,
+      fn: () => this.wallet.getFinalizeWithdrawalParams(operation.txHash),
+    });
+    await this.simulateFinalization(this.chainId, params);
+    return true;
+  }
+
+  protected async step() {
+    let operation = pendingOperations.list(OPERATION_TYPE)[0];
+    if (operation == null) {
+      // an already initiated withdrawal keeps being tracked, only new ones are held back
+      if (isSpendBudgetExhausted()) {
+        this.logger.warn("Spend budget exhausted, not initiating a withdrawal");
+        return;
+      }
+      operation = await this.initiateWithdrawal();
+    }
+    const elapsedSec = (Date.now() - operation.startedAt) / 1000;
+    try {
+      if (!(await this.isFinalizable(operation))) {
+        if (elapsedSec * SEC > WITHDRAWAL_E2E_SLA) {
+          this.metricSlaMet.set(0);
+          this.logger.warn(`Withdrawal ${operation.txHash} not finalizable after ${elapsedSec.toFixed(0)} seconds`);
+        }
+        return;
+      }
+      // eslint-disable-next-line @typescript-eslint/no-explicit-any
+    } catch (error: any) {
+      // a transient failure must not throw away a withdrawal tracked for hours, it's checked again next cycle
+      if (!(error instanceof WatchdogError)) {
+        this.logger.warn(`Failed to check withdrawal ${operation.txHash}, retrying next cycle: ${error?.message}`);
+        return;
+      }
+      this.logger.error(`Error while tracking withdrawal ${operation.txHash}: ${error?.message}`, error?.stack);
+      pendingOperations.remove(operation.txHash);
+      this.metricRecorder.manualRecordStatus(Status.FAIL, elapsedSec);
+      return;
+    }
+    const slaMet = elapsedSec * SEC <= WITHDRAWAL_E2E_SLA;
+    this.logger.info(`Withdrawal ${operation.txHash} finalizable after ${elapsedSec.toFixed(0)} seconds`);
+    pendingOperations.remove(operation.txHash);
+    this.metricE2eLatency.set(elapsedSec);
+    this.metricSlaMet.set(slaMet ? 1 : 0);
+    this.metricRecorder.manualRecordStatus(slaMet ? Status.OK : Status.FAIL, elapsedSec);
+  }
+
+  public async run() {
+    await this.detectFeeModel();
+    this.chainId = (await this.wallet._providerL2().getNetwork()).chainId;
+    while (true) {
+      const waitNextExecution = this.startCycle(this.intervalMs);
+      await checkChainIdUnchanged(this.wallet._providerL2());
+      try {
+        await this.l2WalletLock.withLock(() => this.step());
+        // eslint-disable-next-line @typescript-eslint/no-explicit-any
+      } catch (error: any) {
+        this.logger.error("Withdrawal e2e error: " + error?.message, error?.stack);
+        this.metricRecorder.manualRecordStatus(Status.FAIL, 0);
+      }
+      await waitNextExecution();
+    }
+  }
+}
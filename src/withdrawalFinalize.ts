@@ -4,7 +4,7 @@ import { Gauge } from "prom-client";
 import { L2_BASE_TOKEN_ADDRESS, isAddressEq } from "zksync-ethers/build/utils";
 
 import { Status } from "./flowMetric";
-import { SEC, MIN, unwrap, timeoutPromise } from "./utils";
+import { SEC, MIN, unwrap } from "./utils";
 import { WithdrawalBaseFlow, STEPS } from "./withdrawalBase";
 
 import type { BigNumberish } from "ethers";
@@ -12,7 +12,6 @@ import type { Wallet } from "zksync-ethers";
 
 const FLOW_NAME = "withdrawalFinalize";
 const FINALIZE_INTERVAL = +(process.env.FLOW_WITHDRAWAL_FINALIZE_INTERVAL ?? 15 * MIN);
-const PRE_V26_BRIDGES = process.env.PRE_V26_BRIDGES === "1";
 
 export class WithdrawalFinalizeFlow extends WithdrawalBaseFlow {
   private metricTimeSinceLastFinalizableWithdrawal: Gauge;
@@ -54,16 +53,15 @@ export class WithdrawalFinalizeFlow extends WithdrawalBaseFlow {
       this.logger.info(`Simulating finalization for withdrawal hash: ${withdrawalHash}`);
 
       // Get finalization parameters
-      const { l1BatchNumber, l2MessageIndex, l2TxNumberInBlock, message, sender, proof } =
-        await this.metricRecorder.stepExecution({
-          stepName: STEPS.get_finalization_params,
-          stepTimeoutMs: 10 * SEC,
-          fn: async () => {
-            return this.wallet.getFinalizeWithdrawalParams(withdrawalHash);
-          },
-        });
-
-      if (!isAddressEq(sender, L2_BASE_TOKEN_ADDRESS)) {
+      const params = await this.metricRecorder.stepExecution({
+        stepName: STEPS.get_finalization_params,
+        stepTimeoutMs: 10 * SEC,
+        fn: async () => {
+          return this.wallet.getFinalizeWithdrawalParams(withdrawalHash);
+        },
+      });
+
+      if (!isAddressEq(params.sender, L2_BASE_TOKEN_ADDRESS)) {
         throw new Error(`Withdrawal ${withdrawalHash} is not a base token withdrawal`);
       }
 
@@ -73,43 +71,7 @@ export class WithdrawalFinalizeFlow extends WithdrawalBaseFlow {
         return Status.SKIP;
       }
 
-      if (PRE_V26_BRIDGES) {
-        // Legacy shared bridge
-        const bridges = await this.wallet.getL1BridgeContracts();
-        await this.metricRecorder.stepExecution({
-          stepName: STEPS.l1_simulation,
-          stepTimeoutMs: 10 * SEC,
-          fn: async ({ recordStepGas }) => {
-            const gas = await bridges.shared.finalizeWithdrawal.estimateGas(
-              this.chainId,
-              l1BatchNumber as BigNumberish,
-              l2MessageIndex as BigNumberish,
-              l2TxNumberInBlock as BigNumberish,
-              message,
-              proof
-            );
-            recordStepGas(gas);
-          },
-        });
-      } else {
-        const l1Nullifier = await this.wallet.getL1Nullifier();
-        await this.metricRecorder.stepExecution({
-          stepName: STEPS.l1_simulation,
-          stepTimeoutMs: 10 * SEC,
-          fn: async ({ recordStepGas }) => {
-            const gas = await l1Nullifier.finalizeDeposit.estimateGas({
-              chainId: this.chainId,
-              l2BatchNumber: l1BatchNumber as BigNumberish,
-              l2MessageIndex: l2MessageIndex as BigNumberish,
-              l2Sender: sender,
-              l2TxNumberInBatch: l2TxNumberInBlock as BigNumberish,
-              message,
-              merkleProof: proof,
-            });
-            recordStepGas(gas);
-          },
-        });
-      }
+      await this.simulateFinalization(this.chainId, params);
 
       this.logger.info(`Finalization simulation for withdrawal ${withdrawalHash} successful`);
 
@@ -126,10 +88,10 @@ export class WithdrawalFinalizeFlow extends WithdrawalBaseFlow {
     this.logger.info(`Starting withdrawal finalize flow with interval ${this.intervalMs / MIN} minutes`);
     this.chainId = (await this.wallet._providerL2().getNetwork()).chainId;
     while (true) {
-      const nextExecutionWait = timeoutPromise(this.intervalMs);
+      const waitNextExecution = this.startCycle(this.intervalMs);
 
       await this.executeWithdrawalFinalize();
-      await nextExecutionWait;
+      await waitNextExecution();
     }
   }
 }